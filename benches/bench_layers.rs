@@ -3,6 +3,7 @@ extern crate criterion;
 
 use criterion::black_box;
 use criterion::Criterion;
+use hexlit::hex;
 
 use hatchet::layer::ether::Ether;
 use hatchet::layer::ip::{Ipv4, Ipv6};
@@ -32,6 +33,21 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     gen_header_bench!(c, bench_ipv6, Ipv6::default().to_bytes().unwrap(), Ipv6);
     gen_header_bench!(c, bench_tcp, Tcp::default().to_bytes().unwrap(), Tcp);
     gen_header_bench!(c, bench_udp, Udp::default().to_bytes().unwrap(), Udp);
+
+    // MSS, NOP, WScale, SAckOK, Timestamp, NOP, NOP, SAck (2 blocks)
+    gen_header_bench!(
+        c,
+        bench_tcp_options,
+        hex!("04d200500000000100000000f010040000000000020405b4010303070402080ad3845879407337de01010512e4d6c0f0e4d6cba0e4d6d0f0e4d6dba0"),
+        Tcp
+    );
+    // NOP, Record Route (9 addresses)
+    gen_header_bench!(
+        c,
+        bench_ipv4_options,
+        hex!("4f00003c00004000400600007f0000017f00000101072704c0a80101c0a8020100000000000000000000000000000000000000000000000000000000"),
+        Ipv4
+    );
 }
 
 criterion_group!(benches, criterion_benchmark);
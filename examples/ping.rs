@@ -2,7 +2,7 @@ use hatchet::datalink::PacketWrite;
 use hatchet::datalink::{pcap::Pcap, Interface};
 use hatchet::is_layer;
 use hatchet::layer::ether::{Ether, EtherType, MacAddress};
-use hatchet::layer::icmp::{Icmp4, IcmpType};
+use hatchet::layer::icmp::Icmp4;
 use hatchet::layer::ip::{IpProtocol, Ipv4};
 use hatchet::packet::Packet;
 use hexlit::hex;
@@ -37,12 +37,7 @@ fn main() {
             flags: 0b0100,
             ..Default::default()
         }),
-        Box::new(Icmp4 {
-            icmp_type: IcmpType::EchoRequest,
-            data: vec![0xFF, 0xFF],
-            message: 0xDfADBEfF,
-            ..Default::default()
-        }),
+        Box::new(Icmp4::echo_request(0xDEAD, 0xBEEF, vec![0xFF, 0xFF])),
     ]);
 
     echo_request.finalize().unwrap();
@@ -10,9 +10,13 @@ Some interface types are enabled via crate features.
 | [Pnet] | default | Use [libpnet] cross-platform abstraction over a network interface
 | [Pnet] | netmap | Enable [netmap] feature in libpnet to utilize netmap for I/O
 | [Pcap] | pcap | Use libpcap for I/O on a network interface
+| [Tun] | tuntap | Linux TUN device, reads and writes ip packets
+| [Tap] | tuntap | Linux TAP device, reads and writes ethernet frames
 
 [Pnet]: crate::datalink::pnet::Pnet
 [Pcap]: crate::datalink::pcap::Pcap
+[Tun]: crate::datalink::tuntap::Tun
+[Tap]: crate::datalink::tuntap::Tap
 [libpnet]: https://github.com/libpnet/libpnet
 [netmap]: http://info.iet.unipi.it/~luigi/netmap/
 
@@ -38,11 +42,15 @@ pub mod pcapfile;
 #[cfg(feature = "pnet")]
 pub mod pnet;
 
+#[cfg(all(feature = "tuntap", target_os = "linux"))]
+pub mod tuntap;
+
 pub mod error;
 
 use crate::datalink::error::DataLinkError;
 use crate::layer::ether::MacAddress;
 use crate::packet::{Packet, PacketParser};
+use alloc::vec::Vec;
 
 /// A generic Packet interface used to Read and Write packets
 pub struct Interface<R: PacketRead, W: PacketWrite> {
@@ -197,6 +205,48 @@ pub trait PacketWrite {
     fn write(&mut self, packet: Packet) -> Result<(), DataLinkError>;
 }
 
+/**
+Packet writer forwarding each packet to two writers
+
+Useful to capture injected packets, such as writing to a network interface and a pcap file.
+
+# Example
+
+```rust,ignore
+let (_rx, tx) = Interface::init::<Pnet>("lo").unwrap().into_split();
+let pcap = InterfaceWriter::init::<PcapFile>("out.pcap").unwrap();
+
+let mut writer = TeePacketWrite::new(tx, pcap);
+writer.write(packet).unwrap();
+```
+*/
+pub struct TeePacketWrite<A: PacketWrite, B: PacketWrite> {
+    first: A,
+    second: B,
+}
+
+impl<A: PacketWrite, B: PacketWrite> TeePacketWrite<A, B> {
+    /// Create a writer forwarding packets to `first`, then `second`
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// Split into the inner writers
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: PacketWrite, B: PacketWrite> PacketWrite for TeePacketWrite<A, B> {
+    /// Write the packet to both writers
+    ///
+    /// The second writer is not written to if writing to the first writer fails
+    fn write(&mut self, packet: Packet) -> Result<(), DataLinkError> {
+        self.first.write(packet.clone())?;
+        self.second.write(packet)
+    }
+}
+
 /// Unimplemented packet writer
 pub struct UnimplementedWriter;
 impl PacketWrite for UnimplementedWriter {
@@ -290,6 +340,62 @@ where
     }
 }
 
+/**
+Read-only interface merging the packets of several [InterfaceReader]
+
+Readers are read in a round-robin fashion. A reader returning [DataLinkError::Eof] is removed,
+[DataLinkError::Eof] is returned once all readers are exhausted. Other errors are returned for
+the reader they occurred on, the following read continues with the next reader.
+
+When iterating, errors of a reader are skipped. Iteration ends once all readers are exhausted,
+or when each remaining reader fails in turn without returning a packet.
+
+**Note:** readers are read with blocking reads, an idle interface blocks [read](PacketRead::read)
+until it receives a packet. Packets arriving on the other interfaces meanwhile are delayed, and
+are dropped once the buffers of these interfaces are full. This reader is not suitable for
+interfaces with uneven traffic, read each interface from its own thread instead.
+
+# Example
+
+```rust,ignore
+let eth0 = InterfaceReader::init::<Pnet>("eth0").unwrap();
+let eth1 = InterfaceReader::init::<Pnet>("eth1").unwrap();
+
+let mut reader = MultiInterfaceReader::new(vec![eth0, eth1]);
+
+for (_i, pkt) in (&mut reader).enumerate() {
+    println!("Packet: {:?}", pkt);
+}
+```
+*/
+pub struct MultiInterfaceReader<R>
+where
+    R: PacketRead,
+{
+    readers: Vec<InterfaceReader<R>>,
+    next: usize,
+}
+
+impl<R> MultiInterfaceReader<R>
+where
+    R: PacketRead,
+{
+    /// Create a reader from a set of interface readers
+    pub fn new(readers: Vec<InterfaceReader<R>>) -> Self {
+        Self { readers, next: 0 }
+    }
+
+    /// Add an interface reader
+    pub fn push(&mut self, reader: InterfaceReader<R>) {
+        self.readers.push(reader);
+    }
+
+    /// Interface readers which are not exhausted
+    pub fn readers(&self) -> &[InterfaceReader<R>] {
+        &self.readers
+    }
+}
+
 /// Write-only interface
 pub struct InterfaceWriter<W>
 where
@@ -331,6 +437,27 @@ impl<T: PacketRead> PacketRead for InterfaceReader<T> {
     }
 }
 
+impl<T: PacketRead> PacketRead for MultiInterfaceReader<T> {
+    fn read(&mut self) -> Result<Packet, DataLinkError> {
+        while !self.readers.is_empty() {
+            let index = self.next % self.readers.len();
+
+            match self.readers[index].read() {
+                Err(DataLinkError::Eof) => {
+                    self.readers.remove(index);
+                    self.next = index;
+                }
+                res => {
+                    self.next = index + 1;
+                    return res;
+                }
+            }
+        }
+
+        Err(DataLinkError::Eof)
+    }
+}
+
 impl<'a, T: PacketWrite> PacketWrite for InterfaceWriterRef<'a, T> {
     fn write(&mut self, packet: Packet) -> Result<(), DataLinkError> {
         self.writer.write(packet)
@@ -382,9 +509,28 @@ impl<T: PacketRead> Iterator for InterfaceReader<T> {
     }
 }
 
+impl<T: PacketRead> Iterator for MultiInterfaceReader<T> {
+    type Item = Packet;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut failures = 0;
+
+        while failures < self.readers.len() {
+            match self.read() {
+                Ok(packet) => return Some(packet),
+                Err(DataLinkError::Eof) => return None,
+                Err(_e) => failures += 1,
+            }
+        }
+
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[allow(dead_code)]
     struct DummyInterface {
@@ -478,6 +624,112 @@ mod tests {
         }
     }
 
+    /// Reader returning a fixed amount of packets, tagged with the reader id
+    struct CountReader {
+        id: u64,
+        count: usize,
+    }
+
+    impl PacketRead for CountReader {
+        fn read(&mut self) -> Result<Packet, DataLinkError> {
+            if self.count == 0 {
+                return Err(DataLinkError::Eof);
+            }
+            self.count -= 1;
+
+            let mut packet = Packet::new();
+            packet.set_timestamp(core::time::Duration::from_secs(self.id));
+            Ok(packet)
+        }
+    }
+
+    fn count_reader(id: u64, count: usize) -> InterfaceReader<CountReader> {
+        InterfaceReader {
+            reader: CountReader { id, count },
+            metadata: InterfaceMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_multi_interface_reader() {
+        let mut reader = MultiInterfaceReader::new(vec![count_reader(0, 3), count_reader(1, 1)]);
+        reader.push(count_reader(2, 2));
+
+        let ids: Vec<u64> = (&mut reader)
+            .map(|pkt| pkt.timestamp().unwrap().as_secs())
+            .collect();
+        assert_eq!(vec![0, 1, 2, 0, 2, 0], ids);
+
+        assert!(reader.readers().is_empty());
+        assert!(matches!(reader.read(), Err(DataLinkError::Eof)));
+    }
+
+    /// Reader returning scripted results, a packet tagged with the id or an error for `None`
+    struct ScriptReader {
+        results: Vec<Option<u64>>,
+    }
+
+    impl PacketRead for ScriptReader {
+        fn read(&mut self) -> Result<Packet, DataLinkError> {
+            if self.results.is_empty() {
+                return Err(DataLinkError::Eof);
+            }
+
+            match self.results.remove(0) {
+                Some(id) => {
+                    let mut packet = Packet::new();
+                    packet.set_timestamp(core::time::Duration::from_secs(id));
+                    Ok(packet)
+                }
+                None => Err(DataLinkError::BufferError),
+            }
+        }
+    }
+
+    fn script_reader(results: Vec<Option<u64>>) -> InterfaceReader<ScriptReader> {
+        InterfaceReader {
+            reader: ScriptReader { results },
+            metadata: InterfaceMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_multi_interface_reader_errors() {
+        let mut reader = MultiInterfaceReader::new(vec![
+            script_reader(vec![None, Some(0), None, Some(0)]),
+            script_reader(vec![Some(1), Some(1)]),
+        ]);
+
+        // errors are returned for the reader they occurred on, without ending the stream
+        assert!(matches!(reader.read(), Err(DataLinkError::BufferError)));
+        assert_eq!(1, reader.read().unwrap().timestamp().unwrap().as_secs());
+
+        // errors are skipped when iterating
+        let ids: Vec<u64> = (&mut reader)
+            .map(|pkt| pkt.timestamp().unwrap().as_secs())
+            .collect();
+        assert_eq!(vec![0, 1, 0], ids);
+
+        // iteration ends when all readers fail in turn
+        let mut reader = MultiInterfaceReader::new(vec![
+            script_reader(vec![None, Some(0)]),
+            script_reader(vec![None, Some(1)]),
+        ]);
+        assert!(reader.next().is_none());
+        assert_eq!(2, reader.readers().len());
+    }
+
+    #[test]
+    fn test_tee_packet_write() {
+        let mut writer = TeePacketWrite::new(DummyWriter::default(), DummyWriter::default());
+        writer.write(Packet::new()).unwrap();
+        writer.write(Packet::new()).unwrap();
+
+        let (first, second) = writer.into_inner();
+        assert_eq!(2, first.write_count);
+        assert_eq!(2, second.write_count);
+    }
+
     #[test]
     fn test_interface_default() {
         let mut interface = Interface::init::<DummyInterface>("test").unwrap();
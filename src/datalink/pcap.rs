@@ -11,7 +11,7 @@ use crate::{
         Interface, InterfaceMetadata, InterfaceReader, InterfaceWriter, PacketInterfaceRead,
         PacketInterfaceWrite,
     },
-    layer::ether::{Ether, MacAddress},
+    layer::ether::MacAddress,
     packet::{Packet, PacketParser},
 };
 
@@ -119,14 +119,7 @@ impl PacketRead for Pcap {
 
 impl PacketRead for PcapReader {
     fn read(&mut self) -> Result<Packet, DataLinkError> {
-        match self.reader.next() {
-            Ok(packet_bytes) => {
-                let (_rest, packet) = self.packet_parser.parse_packet::<Ether>(packet_bytes)?;
-                // TODO: log warning of un-read data?
-                Ok(packet)
-            }
-            Err(e) => Err(DataLinkError::IoError(e)),
-        }
+        super::pnet::read_packet(self.reader.as_mut(), &self.packet_parser)
     }
 }
 
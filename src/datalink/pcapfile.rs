@@ -3,6 +3,10 @@ Packet interface implementation using `libpcap` to read pcap files
 
 Note: Pcap writing currently not supported
 
+Both microsecond and nanosecond timestamp resolutions are read, detected from the magic number
+of the pcap header. Timestamps are available with nanosecond precision via
+[Packet::timestamp](crate::packet::Packet::timestamp).
+
 libpcap interface exposed via libpnet
 */
 use crate::{
@@ -10,24 +14,21 @@ use crate::{
         error::DataLinkError, InterfaceMetadata, InterfaceReader, InterfaceWriter,
         PacketInterfaceRead, PacketInterfaceWrite, PacketRead, PacketWrite,
     },
-    layer::{ether::Ether, raw::Raw},
-    packet::{Packet, PacketError, PacketParser},
+    packet::{LinkType, Packet, PacketParser},
 };
 use core::convert::TryFrom;
+use core::time::Duration;
 use pcap_file::{pcap::PcapReader, PcapWriter};
 use std::fs::File;
 
 /// Pcap file based interface
 pub struct PcapFile {}
 
-type PcapParserFn =
-    Box<dyn for<'a, 'b> Fn(&'a PacketParser, &'b [u8]) -> Result<(&'b [u8], Packet), PacketError>>;
-
 /// Pcap file reader
 pub struct PcapFileReader {
     packet_parser: PacketParser,
     reader: PcapReader<File>,
-    parser_fn: PcapParserFn,
+    link_type: LinkType,
 }
 
 /// Pcap file writer
@@ -55,37 +56,13 @@ impl PacketInterfaceRead for PcapFile {
         let file_in = File::open(filename)?;
         let reader = PcapReader::new(file_in)?;
 
-        // Initialize the parser based on the pcap header
-        let parser_fn = match reader.header.datalink {
-            pcap_file::DataLink::ETHERNET => {
-                let pfn: PcapParserFn = Box::new(
-                    |packet_parser: &PacketParser,
-                     i: &[u8]|
-                     -> Result<(&[u8], Packet), PacketError> {
-                        packet_parser.parse_packet::<Ether>(i)
-                    },
-                );
-
-                pfn
-            }
-            _ => {
-                let pfn: PcapParserFn = Box::new(
-                    |packet_parser: &PacketParser,
-                     i: &[u8]|
-                     -> Result<(&[u8], Packet), PacketError> {
-                        packet_parser.parse_packet::<Raw>(i)
-                    },
-                );
-
-                pfn
-            }
-        };
+        let link_type = LinkType::from(u32::from(reader.header.datalink));
 
         Ok(InterfaceReader {
             reader: PcapFileReader {
                 packet_parser,
                 reader,
-                parser_fn,
+                link_type,
             },
             metadata: InterfaceMetadata { mac_address: None },
         })
@@ -112,9 +89,12 @@ impl PacketInterfaceWrite for PcapFile {
 impl PacketRead for PcapFileReader {
     fn read(&mut self) -> Result<Packet, DataLinkError> {
         match self.reader.next() {
-            Some(Ok(packet)) => {
-                let (_rest, packet) = (self.parser_fn)(&self.packet_parser, &packet.data)?;
+            Some(Ok(pcap_packet)) => {
+                let (_rest, mut packet) = self
+                    .packet_parser
+                    .parse_for_linktype(self.link_type, &pcap_packet.data)?;
                 // TODO: log warning of un-read data?
+                packet.set_timestamp(pcap_packet.header.timestamp());
                 Ok(packet)
             }
             Some(Err(e)) => Err(e.into()),
@@ -134,15 +114,25 @@ impl PacketWrite for PcapFileWriter {
             ))
         })?;
 
-        let ts = chrono::offset::Utc::now();
-        let ts_sec = u32::try_from(ts.timestamp()).map_err(|_e| {
+        // Use the capture timestamp of the packet if present
+        let ts = match packet.timestamp() {
+            Some(ts) => ts,
+            None => {
+                let now = chrono::offset::Utc::now();
+                let secs = u64::try_from(now.timestamp()).map_err(|_e| {
+                    DataLinkError::PcapError(format!("invalid timestamp {}", now.timestamp()))
+                })?;
+                Duration::new(secs, now.timestamp_subsec_nanos())
+            }
+        };
+        let ts_sec = u32::try_from(ts.as_secs()).map_err(|_e| {
             DataLinkError::PcapError(format!(
                 "failed to convert timestamp {} > {}",
-                ts.timestamp(),
+                ts.as_secs(),
                 u32::MAX
             ))
         })?;
-        let ts_nsec = ts.timestamp_subsec_nanos();
+        let ts_nsec = ts.subsec_nanos();
 
         match self.writer.write(ts_sec, ts_nsec, &data, data_len) {
             Ok(_) => Ok(()),
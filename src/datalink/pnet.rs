@@ -10,6 +10,46 @@ use crate::{
     packet::{Packet, PacketParser},
 };
 use alloc::boxed::Box;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+/// Maximum number of consecutive transient errors before a read fails
+const MAX_READ_RETRIES: usize = 8;
+
+/// Initial delay before retrying a read which would block, doubled on each retry
+const READ_RETRY_BACKOFF: Duration = Duration::from_millis(1);
+
+/// Read and parse the next packet from a receiver
+///
+/// Transient errors are retried up to [MAX_READ_RETRIES] times: reads interrupted by a signal
+/// are retried immediately, reads which would block are retried with an exponential backoff.
+pub(crate) fn read_packet(
+    reader: &mut dyn DataLinkReceiver,
+    packet_parser: &PacketParser,
+) -> Result<Packet, DataLinkError> {
+    let mut retries = 0;
+    let mut backoff = READ_RETRY_BACKOFF;
+
+    loop {
+        match reader.next() {
+            Ok(packet_bytes) => {
+                let (_rest, packet) = packet_parser.parse_packet::<Ether>(packet_bytes)?;
+                // TODO: log warning of un-read data?
+                return Ok(packet);
+            }
+            Err(e) if retries < MAX_READ_RETRIES => match e.kind() {
+                ErrorKind::Interrupted => retries += 1,
+                ErrorKind::WouldBlock => {
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
+                    retries += 1;
+                }
+                _ => return Err(DataLinkError::IoError(e)),
+            },
+            Err(e) => return Err(DataLinkError::IoError(e)),
+        }
+    }
+}
 
 /// Pnet network interface
 pub struct Pnet {
@@ -76,14 +116,7 @@ impl PacketRead for Pnet {
 
 impl PacketRead for PnetReader {
     fn read(&mut self) -> Result<Packet, DataLinkError> {
-        match self.reader.next() {
-            Ok(packet_bytes) => {
-                let (_rest, packet) = self.packet_parser.parse_packet::<Ether>(packet_bytes)?;
-                // TODO: log warning of un-read data?
-                Ok(packet)
-            }
-            Err(e) => Err(DataLinkError::IoError(e)),
-        }
+        read_packet(self.reader.as_mut(), &self.packet_parser)
     }
 }
 
@@ -103,3 +136,71 @@ impl PacketWrite for PnetWriter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::ether::EtherType;
+    use alloc::{vec, vec::Vec};
+    use hexlit::hex;
+    use std::io;
+
+    /// Receiver returning the given errors before returning a frame
+    struct MockReceiver {
+        errors: Vec<ErrorKind>,
+        frame: Vec<u8>,
+    }
+
+    impl DataLinkReceiver for MockReceiver {
+        fn next(&mut self) -> io::Result<&[u8]> {
+            match self.errors.pop() {
+                Some(kind) => Err(io::Error::from(kind)),
+                None => Ok(&self.frame),
+            }
+        }
+    }
+
+    fn mock_reader(errors: Vec<ErrorKind>) -> PnetReader {
+        PnetReader {
+            packet_parser: PacketParser::new(),
+            reader: Box::new(MockReceiver {
+                errors,
+                frame: hex!("feff200001000000010000000800").to_vec(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_pnet_read_retry() {
+        let mut reader = mock_reader(vec![
+            ErrorKind::Interrupted,
+            ErrorKind::WouldBlock,
+            ErrorKind::Interrupted,
+        ]);
+
+        let packet = reader.read().unwrap();
+        let ether = crate::get_layer!(packet.layers()[0], Ether).unwrap();
+        assert_eq!(EtherType::IPv4, ether.ether_type);
+    }
+
+    #[test]
+    fn test_pnet_read_retry_exhausted() {
+        let mut reader = mock_reader(vec![ErrorKind::Interrupted; MAX_READ_RETRIES + 1]);
+        assert!(matches!(
+            reader.read(),
+            Err(DataLinkError::IoError(e)) if e.kind() == ErrorKind::Interrupted
+        ));
+
+        // Following read succeeds
+        assert!(reader.read().is_ok());
+    }
+
+    #[test]
+    fn test_pnet_read_error() {
+        let mut reader = mock_reader(vec![ErrorKind::PermissionDenied]);
+        assert!(matches!(
+            reader.read(),
+            Err(DataLinkError::IoError(e)) if e.kind() == ErrorKind::PermissionDenied
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+/*!
+Packet interface implementation using Linux TUN/TAP devices
+
+A [Tun] device carries IP packets without link-layer framing, packets are read starting at
+[Ipv4] or [Ipv6] depending on the ip version. A [Tap] device carries Ethernet frames, packets
+are read starting at [Ether].
+
+The device is created if it does not exist, this requires `CAP_NET_ADMIN`.
+
+[Ipv4]: crate::layer::ip::Ipv4
+[Ipv6]: crate::layer::ip::Ipv6
+[Ether]: crate::layer::ether::Ether
+*/
+use super::{DataLinkError, PacketInterface, PacketRead, PacketWrite};
+use crate::{
+    datalink::{
+        Interface, InterfaceMetadata, InterfaceReader, InterfaceWriter, PacketInterfaceRead,
+        PacketInterfaceWrite,
+    },
+    layer::ether::MacAddress,
+    packet::{LinkType, Packet, PacketParser},
+};
+use alloc::{vec, vec::Vec};
+use pnet::datalink::{self, NetworkInterface};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::io::AsRawFd;
+
+const TUN_DEVICE: &str = "/dev/net/tun";
+const TUNSETIFF: libc::c_ulong = 0x400454ca;
+const IFF_TUN: libc::c_short = 0x0001;
+const IFF_TAP: libc::c_short = 0x0002;
+const IFF_NO_PI: libc::c_short = 0x1000;
+
+/// Maximum size of a packet read from the device
+const READ_BUFFER_SIZE: usize = 65535 + 14;
+
+/// Interface request, see `netdevice(7)`
+#[repr(C)]
+struct IfReq {
+    name: [libc::c_char; libc::IFNAMSIZ],
+    flags: libc::c_short,
+    _pad: [u8; 22],
+}
+
+/// Kind of TUN/TAP device
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TunTapMode {
+    /// Layer 3 device, packets start at the ip header
+    Tun,
+    /// Layer 2 device, packets start at the ethernet header
+    Tap,
+}
+
+impl TunTapMode {
+    /// Link type of the packets carried by the device
+    pub fn link_type(&self) -> LinkType {
+        match self {
+            TunTapMode::Tun => LinkType::RawIp,
+            TunTapMode::Tap => LinkType::Ethernet,
+        }
+    }
+}
+
+/// TUN network interface
+pub struct Tun {}
+
+/// TAP network interface
+pub struct Tap {}
+
+/// TUN/TAP reader
+pub struct TunTapReader {
+    packet_parser: PacketParser,
+    mode: TunTapMode,
+    file: File,
+    buffer: Vec<u8>,
+}
+
+/// TUN/TAP writer
+pub struct TunTapWriter {
+    file: File,
+}
+
+/// Open the TUN/TAP device, creating it if it does not exist
+fn open_device(name: &str, mode: TunTapMode) -> Result<File, DataLinkError> {
+    if name.len() >= libc::IFNAMSIZ {
+        return Err(DataLinkError::InterfaceNotFound);
+    }
+
+    let file = OpenOptions::new().read(true).write(true).open(TUN_DEVICE)?;
+
+    let mut ifreq = IfReq {
+        name: [0; libc::IFNAMSIZ],
+        flags: IFF_NO_PI
+            | match mode {
+                TunTapMode::Tun => IFF_TUN,
+                TunTapMode::Tap => IFF_TAP,
+            },
+        _pad: [0; 22],
+    };
+    for (dst, src) in ifreq.name.iter_mut().zip(name.bytes()) {
+        *dst = src as libc::c_char;
+    }
+
+    // SAFETY: `ifreq` is a valid `struct ifreq` which outlives the call
+    let ret = unsafe { libc::ioctl(file.as_raw_fd(), TUNSETIFF as _, &mut ifreq) };
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(file)
+}
+
+fn init_tuntap(
+    name: &str,
+    mode: TunTapMode,
+    packet_parser: PacketParser,
+) -> Result<Interface<TunTapReader, TunTapWriter>, DataLinkError> {
+    let file = open_device(name, mode)?;
+
+    let mac_address = match mode {
+        TunTapMode::Tun => None,
+        TunTapMode::Tap => datalink::interfaces()
+            .into_iter()
+            .find(|iface: &NetworkInterface| iface.name == name)
+            .and_then(|iface| iface.mac)
+            .map(|v| MacAddress(v.octets())),
+    };
+
+    Ok(Interface {
+        reader: TunTapReader {
+            packet_parser,
+            mode,
+            file: file.try_clone()?,
+            buffer: vec![0; READ_BUFFER_SIZE],
+        },
+        writer: TunTapWriter { file },
+        metadata: InterfaceMetadata { mac_address },
+    })
+}
+
+impl PacketInterface for Tun {
+    type Reader = TunTapReader;
+    type Writer = TunTapWriter;
+
+    fn init(name: &str) -> Result<Interface<Self::Reader, Self::Writer>, DataLinkError> {
+        <Self as PacketInterface>::init_with_parser(name, PacketParser::new())
+    }
+
+    fn init_with_parser(
+        name: &str,
+        packet_parser: PacketParser,
+    ) -> Result<Interface<Self::Reader, Self::Writer>, DataLinkError> {
+        init_tuntap(name, TunTapMode::Tun, packet_parser)
+    }
+}
+
+impl PacketInterface for Tap {
+    type Reader = TunTapReader;
+    type Writer = TunTapWriter;
+
+    fn init(name: &str) -> Result<Interface<Self::Reader, Self::Writer>, DataLinkError> {
+        <Self as PacketInterface>::init_with_parser(name, PacketParser::new())
+    }
+
+    fn init_with_parser(
+        name: &str,
+        packet_parser: PacketParser,
+    ) -> Result<Interface<Self::Reader, Self::Writer>, DataLinkError> {
+        init_tuntap(name, TunTapMode::Tap, packet_parser)
+    }
+}
+
+impl PacketInterfaceRead for Tun {
+    type Reader = TunTapReader;
+
+    fn init(name: &str) -> Result<InterfaceReader<Self::Reader>, DataLinkError> {
+        <Self as PacketInterfaceRead>::init_with_parser(name, PacketParser::new())
+    }
+
+    fn init_with_parser(
+        name: &str,
+        packet_parser: PacketParser,
+    ) -> Result<InterfaceReader<Self::Reader>, DataLinkError> {
+        let (reader, _writer) =
+            <Tun as PacketInterface>::init_with_parser(name, packet_parser)?.into_split();
+        Ok(reader)
+    }
+}
+
+impl PacketInterfaceRead for Tap {
+    type Reader = TunTapReader;
+
+    fn init(name: &str) -> Result<InterfaceReader<Self::Reader>, DataLinkError> {
+        <Self as PacketInterfaceRead>::init_with_parser(name, PacketParser::new())
+    }
+
+    fn init_with_parser(
+        name: &str,
+        packet_parser: PacketParser,
+    ) -> Result<InterfaceReader<Self::Reader>, DataLinkError> {
+        let (reader, _writer) =
+            <Tap as PacketInterface>::init_with_parser(name, packet_parser)?.into_split();
+        Ok(reader)
+    }
+}
+
+impl PacketInterfaceWrite for Tun {
+    type Writer = TunTapWriter;
+
+    fn init(name: &str) -> Result<InterfaceWriter<Self::Writer>, DataLinkError> {
+        let (_reader, writer) = <Self as PacketInterface>::init(name)?.into_split();
+        Ok(writer)
+    }
+}
+
+impl PacketInterfaceWrite for Tap {
+    type Writer = TunTapWriter;
+
+    fn init(name: &str) -> Result<InterfaceWriter<Self::Writer>, DataLinkError> {
+        let (_reader, writer) = <Self as PacketInterface>::init(name)?.into_split();
+        Ok(writer)
+    }
+}
+
+impl TunTapReader {
+    /// Kind of device being read
+    pub fn mode(&self) -> TunTapMode {
+        self.mode
+    }
+}
+
+impl PacketRead for TunTapReader {
+    fn read(&mut self) -> Result<Packet, DataLinkError> {
+        let len = self.file.read(&mut self.buffer)?;
+        let packet_bytes = &self.buffer[..len];
+
+        let (_rest, packet) = self
+            .packet_parser
+            .parse_for_linktype(self.mode.link_type(), packet_bytes)?;
+        // TODO: log warning of un-read data?
+
+        Ok(packet)
+    }
+}
+
+impl PacketWrite for TunTapWriter {
+    fn write(&mut self, packet: Packet) -> Result<(), DataLinkError> {
+        let bytes = packet.to_bytes()?;
+        self.file.write_all(&bytes)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,373 @@
+/*!
+CDP layer
+
+Cisco Discovery Protocol, carried over 802.3 [LLC/SNAP](crate::layer::llc) with OUI
+[CDP_SNAP_OUI] and protocol id [CDP_SNAP_PROTOCOL_ID]
+*/
+
+use crate::layer::ether::MacAddress;
+use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
+use alloc::{format, string::ToString, vec::Vec};
+use core::convert::TryFrom;
+use deku::bitvec::{BitSlice, Msb0};
+use deku::prelude::*;
+
+/// SNAP organizationally unique identifier of Cisco
+pub const CDP_SNAP_OUI: u32 = 0x00000C;
+
+/// SNAP protocol id assigned to CDP
+pub const CDP_SNAP_PROTOCOL_ID: u16 = 0x2000;
+
+/// Multicast destination address of CDP announcements
+pub const CDP_MULTICAST: MacAddress = MacAddress([0x01, 0x00, 0x0C, 0xCC, 0xCC, 0xCC]);
+
+/// Length of the value of the [Capabilities](CdpTlv::Capabilities) TLV
+const CAPABILITIES_VALUE_LENGTH: usize = 4;
+
+/// Cdp TLV length given the length of the value, accounting for the 4 byte type/length header
+fn tlv_length(value_len: usize) -> Result<u16, DekuError> {
+    Ok(u16::try_from(value_len.checked_add(4).ok_or_else(
+        || DekuError::Parse("overflow when updating cdp tlv length".to_string()),
+    )?)?)
+}
+
+/// Length of the value of the [Addresses](CdpTlv::Addresses) TLV
+fn addresses_value_length(addresses: &[CdpAddress]) -> usize {
+    // 4 bytes for the number of addresses, 4 bytes of fixed fields per address
+    addresses.iter().fold(4, |acc, address| {
+        acc + 4 + address.protocol.len() + address.address.len()
+    })
+}
+
+/// Cdp address entry of the [Addresses](CdpTlv::Addresses) TLV
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(ctx = "endian: deku::ctx::Endian", endian = "endian")]
+pub struct CdpAddress {
+    /// Protocol type (1 = NLPID, 2 = 802.2)
+    pub protocol_type: u8,
+    /// Length of the protocol field
+    #[deku(update = "self.protocol.len()")]
+    pub protocol_length: u8,
+    /// Protocol (0xCC = Ipv4 for NLPID)
+    #[deku(count = "protocol_length")]
+    pub protocol: Vec<u8>,
+    /// Length of the address field
+    #[deku(update = "self.address.len()")]
+    pub address_length: u16,
+    /// Address
+    #[deku(count = "address_length")]
+    pub address: Vec<u8>,
+}
+
+/// Cdp TLV
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(
+    type = "u16",
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "deku::ctx::Endian::Big"
+)]
+#[non_exhaustive]
+pub enum CdpTlv {
+    /// Device ID
+    #[deku(id = "0x0001")]
+    DeviceId {
+        /// TLV length, including the type and length fields
+        #[deku(update = "tlv_length(value.len())?")]
+        length: u16,
+        /// Device ID
+        #[deku(
+            count = "length.checked_sub(4).ok_or_else(|| DekuError::Parse(\"overflow when parsing cdp tlv\".to_string()))?"
+        )]
+        value: Vec<u8>,
+    },
+    /// Addresses
+    #[deku(id = "0x0002")]
+    Addresses {
+        /// TLV length, including the type and length fields
+        #[deku(update = "tlv_length(addresses_value_length(addresses))?")]
+        length: u16,
+        /// Number of addresses
+        #[deku(update = "addresses.len()")]
+        count: u32,
+        /// Addresses
+        #[deku(count = "count")]
+        addresses: Vec<CdpAddress>,
+    },
+    /// Port ID
+    #[deku(id = "0x0003")]
+    PortId {
+        /// TLV length, including the type and length fields
+        #[deku(update = "tlv_length(value.len())?")]
+        length: u16,
+        /// Port ID
+        #[deku(
+            count = "length.checked_sub(4).ok_or_else(|| DekuError::Parse(\"overflow when parsing cdp tlv\".to_string()))?"
+        )]
+        value: Vec<u8>,
+    },
+    /// Capabilities
+    #[deku(id = "0x0004")]
+    Capabilities {
+        /// TLV length, including the type and length fields
+        #[deku(
+            assert = "usize::from(*length) == CAPABILITIES_VALUE_LENGTH + 4",
+            update = "tlv_length(CAPABILITIES_VALUE_LENGTH)?"
+        )]
+        length: u16,
+        /// Capability bitmask
+        capabilities: u32,
+    },
+    /// Software Version
+    #[deku(id = "0x0005")]
+    SoftwareVersion {
+        /// TLV length, including the type and length fields
+        #[deku(update = "tlv_length(value.len())?")]
+        length: u16,
+        /// Software version
+        #[deku(
+            count = "length.checked_sub(4).ok_or_else(|| DekuError::Parse(\"overflow when parsing cdp tlv\".to_string()))?"
+        )]
+        value: Vec<u8>,
+    },
+    /// Platform
+    #[deku(id = "0x0006")]
+    Platform {
+        /// TLV length, including the type and length fields
+        #[deku(update = "tlv_length(value.len())?")]
+        length: u16,
+        /// Platform
+        #[deku(
+            count = "length.checked_sub(4).ok_or_else(|| DekuError::Parse(\"overflow when parsing cdp tlv\".to_string()))?"
+        )]
+        value: Vec<u8>,
+    },
+    /// Unknown TLV
+    #[deku(id_pat = "_")]
+    Unknown {
+        /// TLV type
+        type_: u16,
+        /// TLV length, including the type and length fields
+        #[deku(update = "tlv_length(value.len())?")]
+        length: u16,
+        /// TLV value
+        #[deku(
+            count = "length.checked_sub(4).ok_or_else(|| DekuError::Parse(\"overflow when parsing cdp tlv\".to_string()))?"
+        )]
+        value: Vec<u8>,
+    },
+}
+
+/**
+CDP Header
+
+```text
+ 0                   1                   2                   3
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|    Version    |      TTL      |            Checksum           |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|              Type             |             Length            |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|                             Value                             |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+```
+*/
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(endian = "big")]
+pub struct Cdp {
+    /// Version
+    pub version: u8,
+    /// Time To Live in seconds
+    pub ttl: u8,
+    /// Checksum
+    pub checksum: u16,
+    /// List of cdp TLVs
+    #[deku(reader = "Cdp::read_tlvs(deku::rest)")]
+    pub tlvs: Vec<CdpTlv>,
+}
+
+impl Cdp {
+    /// Read all cdp TLVs
+    fn read_tlvs(
+        mut rest: &BitSlice<Msb0, u8>,
+    ) -> Result<(&BitSlice<Msb0, u8>, Vec<CdpTlv>), DekuError> {
+        let mut tlvs = Vec::new();
+        while !rest.is_empty() {
+            let (new_rest, tlv) = CdpTlv::read(rest, deku::ctx::Endian::Big)?;
+
+            tlvs.push(tlv);
+
+            rest = new_rest;
+        }
+
+        Ok((rest, tlvs))
+    }
+
+    /// Update the checksum field
+    pub fn update_checksum(&mut self) -> Result<(), LayerError> {
+        let mut cdp = LayerExt::to_bytes(self)?;
+
+        // Bytes 2, 3 are the checksum. Clear them and re-calculate.
+        cdp[2] = 0x00;
+        cdp[3] = 0x00;
+
+        self.checksum = super::ip::checksum(&cdp);
+
+        Ok(())
+    }
+}
+
+impl Default for Cdp {
+    fn default() -> Self {
+        Cdp {
+            version: 2,
+            ttl: 180,
+            checksum: 0,
+            tlvs: Vec::new(),
+        }
+    }
+}
+
+impl Layer for Cdp {}
+impl LayerExt for Cdp {
+    fn finalize(&mut self, _prev: &[LayerOwned], _next: &[LayerOwned]) -> Result<(), LayerError> {
+        for tlv in self.tlvs.iter_mut() {
+            if let CdpTlv::Addresses { addresses, .. } = tlv {
+                for address in addresses.iter_mut() {
+                    address.update()?;
+                }
+            }
+
+            tlv.update()?;
+        }
+
+        self.update_checksum()?;
+
+        Ok(())
+    }
+
+    fn parse(input: &[u8]) -> Result<(&[u8], Self), LayerError>
+    where
+        Self: Sized,
+    {
+        let ((rest, bit_offset), cdp) = Cdp::from_bytes((input, 0))?;
+        debug_assert_eq!(0, bit_offset);
+        Ok((rest, cdp))
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
+        Ok(DekuContainerWrite::to_bytes(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hexlit::hex;
+    use rstest::*;
+    use std::convert::TryFrom;
+
+    #[rstest(input, expected,
+        case(
+            &hex!("02b475070001000c737769746368303100020011000000010101cc0004c0a80001000300094661302f310004000800000028"),
+            Cdp {
+                version: 2,
+                ttl: 180,
+                checksum: 0x7507,
+                tlvs: vec![
+                    CdpTlv::DeviceId { length: 12, value: b"switch01".to_vec() },
+                    CdpTlv::Addresses {
+                        length: 17,
+                        count: 1,
+                        addresses: vec![CdpAddress {
+                            protocol_type: 1,
+                            protocol_length: 1,
+                            protocol: vec![0xcc],
+                            address_length: 4,
+                            address: vec![192, 168, 0, 1],
+                        }],
+                    },
+                    CdpTlv::PortId { length: 9, value: b"Fa0/1".to_vec() },
+                    CdpTlv::Capabilities { length: 8, capabilities: 0x28 },
+                ],
+            },
+        ),
+        case::unknown(
+            &hex!("02b40000000a00060102"),
+            Cdp {
+                version: 2,
+                ttl: 180,
+                checksum: 0,
+                tlvs: vec![CdpTlv::Unknown { type_: 0x0a, length: 6, value: vec![0x01, 0x02] }],
+            },
+        ),
+        #[should_panic(expected = "CdpTlv.length field failed assertion")]
+        case::capabilities_length(
+            &hex!("02b40000000400060028"),
+            Cdp::default(),
+        ),
+        #[should_panic(expected = "Parse(\"overflow when parsing cdp tlv\")")]
+        case::length_underflow(
+            &hex!("02b40000000100020000"),
+            Cdp::default(),
+        ),
+    )]
+    fn test_cdp_rw(input: &[u8], expected: Cdp) {
+        let ret_read = Cdp::try_from(input).unwrap();
+        assert_eq!(expected, ret_read);
+
+        let ret_write = LayerExt::to_bytes(&ret_read).unwrap();
+        assert_eq!(input.to_vec(), ret_write);
+    }
+
+    #[test]
+    fn test_cdp_default() {
+        assert_eq!(
+            Cdp {
+                version: 2,
+                ttl: 180,
+                checksum: 0,
+                tlvs: vec![],
+            },
+            Cdp::default()
+        )
+    }
+
+    #[test]
+    fn test_cdp_finalize() {
+        let mut cdp = Cdp {
+            tlvs: vec![
+                CdpTlv::DeviceId {
+                    length: 0,
+                    value: b"switch01".to_vec(),
+                },
+                CdpTlv::Addresses {
+                    length: 0,
+                    count: 0,
+                    addresses: vec![CdpAddress {
+                        protocol_type: 1,
+                        protocol_length: 0,
+                        protocol: vec![0xcc],
+                        address_length: 0,
+                        address: vec![192, 168, 0, 1],
+                    }],
+                },
+                CdpTlv::PortId {
+                    length: 0,
+                    value: b"Fa0/1".to_vec(),
+                },
+                CdpTlv::Capabilities {
+                    length: 0,
+                    capabilities: 0x28,
+                },
+            ],
+            ..Default::default()
+        };
+
+        cdp.finalize(&[], &[]).unwrap();
+
+        let expected = hex!("02b475070001000c737769746368303100020011000000010101cc0004c0a80001000300094661302f310004000800000028");
+        assert_eq!(expected.to_vec(), LayerExt::to_bytes(&cdp).unwrap());
+    }
+}
@@ -0,0 +1,535 @@
+/*!
+DNS layer
+
+Domain Name System messages, carried over [Udp](crate::layer::udp::Udp) as [Dns] or over
+[Tcp](crate::layer::tcp::Tcp) as [DnsTcp], prefixed by the length of the message.
+
+Names are kept as their sequence of labels, including compression pointers, such that
+a parsed message serializes to the same bytes.
+*/
+
+use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::convert::TryFrom;
+use deku::bitvec::{BitView, Msb0};
+use deku::prelude::*;
+
+/// Length of the dns header in bytes
+pub const DNS_HEADER_LENGTH: usize = 12;
+
+/// Maximum number of compression pointers followed when decoding a name
+///
+/// A name is at most 255 bytes, a pointer is 2 bytes, this bounds pointer loops.
+const MAX_DNS_POINTERS: usize = 128;
+
+/// Maximum length of a decoded name
+const MAX_DNS_NAME_LENGTH: usize = 255;
+
+/// Dns name label
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(
+    type = "u8",
+    bits = "2",
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian"
+)]
+pub enum DnsLabel {
+    /// Label, a zero length label terminates the name
+    #[deku(id = "0b00")]
+    Label {
+        /// Length of the label
+        #[deku(bits = "6", update = "value.len()")]
+        length: u8,
+        /// Label value
+        #[deku(count = "length")]
+        value: Vec<u8>,
+    },
+    /// Compression pointer, offset from the start of the message to the rest of the name
+    #[deku(id = "0b11")]
+    Pointer {
+        /// Offset in bytes
+        #[deku(bits = "14")]
+        offset: u16,
+    },
+}
+
+impl DnsLabel {
+    /// Returns true if the label terminates the name
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            DnsLabel::Label { length, .. } => *length == 0,
+            DnsLabel::Pointer { .. } => true,
+        }
+    }
+}
+
+/// Dns name, a sequence of labels
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(endian = "endian", ctx = "endian: deku::ctx::Endian")]
+pub struct DnsName {
+    /// Labels, ending with a zero length label or a compression pointer
+    #[deku(until = "|label: &DnsLabel| label.is_terminal()")]
+    pub labels: Vec<DnsLabel>,
+}
+
+impl DnsName {
+    /// Create an uncompressed name from its dotted representation, such as `example.com`
+    pub fn new(name: &str) -> Result<Self, LayerError> {
+        let mut labels = Vec::new();
+        for label in name.split('.').filter(|label| !label.is_empty()) {
+            let length = u8::try_from(label.len())
+                .ok()
+                .filter(|length| *length < 64)
+                .ok_or_else(|| LayerError::Finalize(format!("dns label too long: {}", label)))?;
+
+            labels.push(DnsLabel::Label {
+                length,
+                value: label.as_bytes().to_vec(),
+            });
+        }
+
+        labels.push(DnsLabel::Label {
+            length: 0,
+            value: Vec::new(),
+        });
+
+        Ok(DnsName { labels })
+    }
+
+    /// Decode the name to its dotted representation, such as `example.com`
+    ///
+    /// `message` is the serialized dns message the name is part of, used to follow
+    /// compression pointers. The root name decodes to an empty string. Names longer than
+    /// 255 bytes are an error.
+    pub fn decode(&self, message: &[u8]) -> Result<String, LayerError> {
+        let mut decoded = String::new();
+
+        let mut labels = self.labels.clone();
+        let mut pointers = 0;
+        loop {
+            let mut next_offset = None;
+            for label in labels.iter() {
+                match label {
+                    DnsLabel::Label { value, .. } if !value.is_empty() => {
+                        if !decoded.is_empty() {
+                            decoded.push('.');
+                        }
+                        decoded.push_str(&String::from_utf8_lossy(value));
+
+                        if decoded.len() > MAX_DNS_NAME_LENGTH {
+                            return Err(LayerError::Parse(format!(
+                                "dns name exceeds {} bytes",
+                                MAX_DNS_NAME_LENGTH
+                            )));
+                        }
+                    }
+                    DnsLabel::Label { .. } => {}
+                    DnsLabel::Pointer { offset } => next_offset = Some(usize::from(*offset)),
+                }
+            }
+
+            let offset = match next_offset {
+                Some(offset) => offset,
+                None => break,
+            };
+
+            pointers += 1;
+            if pointers > MAX_DNS_POINTERS {
+                return Err(LayerError::Parse(
+                    "dns name compression pointer loop".to_string(),
+                ));
+            }
+
+            let input = message.get(offset..).ok_or_else(|| {
+                LayerError::Parse(format!("dns name pointer {} out of bounds", offset))
+            })?;
+            let (_rest, name) = DnsName::read(input.view_bits::<Msb0>(), deku::ctx::Endian::Big)?;
+            labels = name.labels;
+        }
+
+        Ok(decoded)
+    }
+
+    fn update_labels(&mut self) -> Result<(), DekuError> {
+        for label in self.labels.iter_mut() {
+            label.update()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for DnsName {
+    fn default() -> Self {
+        DnsName {
+            labels: vec![DnsLabel::Label {
+                length: 0,
+                value: Vec::new(),
+            }],
+        }
+    }
+}
+
+/// Dns question
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(endian = "endian", ctx = "endian: deku::ctx::Endian")]
+pub struct DnsQuestion {
+    /// Name being queried
+    pub name: DnsName,
+    /// Type of the query, such as 1 for an A record
+    pub qtype: u16,
+    /// Class of the query, such as 1 for IN
+    pub qclass: u16,
+}
+
+/// Dns resource record
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(endian = "endian", ctx = "endian: deku::ctx::Endian")]
+pub struct DnsResourceRecord {
+    /// Name the record relates to
+    pub name: DnsName,
+    /// Type of the record
+    pub type_: u16,
+    /// Class of the record
+    pub class: u16,
+    /// Time to live in seconds
+    pub ttl: u32,
+    /// Length of the record data
+    #[deku(update = "self.rdata.len()")]
+    pub rdlength: u16,
+    /// Record data
+    #[deku(count = "rdlength")]
+    pub rdata: Vec<u8>,
+}
+
+/// Dns header flags
+#[derive(Debug, PartialEq, Clone, Default, DekuRead, DekuWrite)]
+#[deku(endian = "endian", ctx = "endian: deku::ctx::Endian")]
+#[allow(missing_docs)]
+pub struct DnsFlags {
+    /// Query (0) or Response (1)
+    #[deku(bits = "1")]
+    pub qr: u8,
+    #[deku(bits = "4")]
+    pub opcode: u8,
+    /// Authoritative Answer
+    #[deku(bits = "1")]
+    pub aa: u8,
+    /// Truncated
+    #[deku(bits = "1")]
+    pub tc: u8,
+    /// Recursion Desired
+    #[deku(bits = "1")]
+    pub rd: u8,
+    /// Recursion Available
+    #[deku(bits = "1")]
+    pub ra: u8,
+    #[deku(bits = "1")]
+    pub z: u8,
+    /// Authentic Data
+    #[deku(bits = "1")]
+    pub ad: u8,
+    /// Checking Disabled
+    #[deku(bits = "1")]
+    pub cd: u8,
+    #[deku(bits = "4")]
+    pub rcode: u8,
+}
+
+/**
+DNS Message
+
+```text
+ 0                   1                   2                   3
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|              ID               |Q| Opcode|A|T|R|R|Z|A|C| RCODE |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|            QDCOUNT            |            ANCOUNT            |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|            NSCOUNT            |            ARCOUNT            |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|           Questions, Answers, Authorities, Additionals        |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+```
+*/
+#[derive(Debug, PartialEq, Clone, Default, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "deku::ctx::Endian::Big"
+)]
+pub struct Dns {
+    /// Identifier
+    pub id: u16,
+    /// Flags
+    pub flags: DnsFlags,
+    /// Number of questions
+    #[deku(update = "self.questions.len()")]
+    pub qdcount: u16,
+    /// Number of answers
+    #[deku(update = "self.answers.len()")]
+    pub ancount: u16,
+    /// Number of authority records
+    #[deku(update = "self.authorities.len()")]
+    pub nscount: u16,
+    /// Number of additional records
+    #[deku(update = "self.additionals.len()")]
+    pub arcount: u16,
+    /// Questions
+    #[deku(count = "qdcount")]
+    pub questions: Vec<DnsQuestion>,
+    /// Answers
+    #[deku(count = "ancount")]
+    pub answers: Vec<DnsResourceRecord>,
+    /// Authority records
+    #[deku(count = "nscount")]
+    pub authorities: Vec<DnsResourceRecord>,
+    /// Additional records
+    #[deku(count = "arcount")]
+    pub additionals: Vec<DnsResourceRecord>,
+}
+
+impl Dns {
+    /// Update the counts, label lengths and record data lengths
+    fn update_lengths(&mut self) -> Result<(), DekuError> {
+        for question in self.questions.iter_mut() {
+            question.name.update_labels()?;
+        }
+
+        for record in self
+            .answers
+            .iter_mut()
+            .chain(self.authorities.iter_mut())
+            .chain(self.additionals.iter_mut())
+        {
+            record.name.update_labels()?;
+            record.update()?;
+        }
+
+        self.update()
+    }
+}
+
+impl Dns {
+    /// Decoded names of the questions, see [DnsName::decode]
+    pub fn question_names(&self) -> Result<Vec<String>, LayerError> {
+        let message = LayerExt::to_bytes(self)?;
+        self.questions
+            .iter()
+            .map(|question| question.name.decode(&message))
+            .collect()
+    }
+}
+
+impl Layer for Dns {}
+impl LayerExt for Dns {
+    fn finalize(&mut self, _prev: &[LayerOwned], _next: &[LayerOwned]) -> Result<(), LayerError> {
+        self.update_lengths()?;
+        Ok(())
+    }
+
+    fn parse(input: &[u8]) -> Result<(&[u8], Self), LayerError>
+    where
+        Self: Sized,
+    {
+        let ((rest, bit_offset), dns) = Dns::from_bytes((input, 0))?;
+        debug_assert_eq!(0, bit_offset);
+        Ok((rest, dns))
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
+        Ok(DekuContainerWrite::to_bytes(self)?)
+    }
+}
+
+/// Dns message over tcp, prefixed by its length
+#[derive(Debug, PartialEq, Clone, Default, DekuRead, DekuWrite)]
+#[deku(endian = "big")]
+pub struct DnsTcp {
+    /// Length of the dns message
+    pub length: u16,
+    /// Dns message
+    pub dns: Dns,
+}
+
+impl Layer for DnsTcp {}
+impl LayerExt for DnsTcp {
+    fn finalize(&mut self, _prev: &[LayerOwned], _next: &[LayerOwned]) -> Result<(), LayerError> {
+        self.dns.update_lengths()?;
+
+        self.length = u16::try_from(self.dns.length()?).map_err(|_e| {
+            LayerError::Finalize("Could not convert dns length to u16".to_string())
+        })?;
+
+        Ok(())
+    }
+
+    fn parse(input: &[u8]) -> Result<(&[u8], Self), LayerError>
+    where
+        Self: Sized,
+    {
+        let ((rest, bit_offset), dns_tcp) = DnsTcp::from_bytes((input, 0))?;
+        debug_assert_eq!(0, bit_offset);
+        Ok((rest, dns_tcp))
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
+        Ok(DekuContainerWrite::to_bytes(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hexlit::hex;
+    use rstest::*;
+    use std::convert::TryFrom;
+
+    fn label(value: &[u8]) -> DnsLabel {
+        DnsLabel::Label {
+            length: value.len() as u8,
+            value: value.to_vec(),
+        }
+    }
+
+    #[rstest(input, expected,
+        case::query(
+            &hex!("123401000001000000000000076578616d706c6503636f6d0000010001"),
+            Dns {
+                id: 0x1234,
+                flags: DnsFlags { rd: 1, ..Default::default() },
+                qdcount: 1,
+                questions: vec![DnsQuestion {
+                    name: DnsName { labels: vec![label(b"example"), label(b"com"), label(b"")] },
+                    qtype: 1,
+                    qclass: 1,
+                }],
+                ..Default::default()
+            },
+        ),
+        case::response_compressed(
+            &hex!("123481800001000100000000076578616d706c6503636f6d0000010001c00c000100010000012c00045db8d822"),
+            Dns {
+                id: 0x1234,
+                flags: DnsFlags { qr: 1, rd: 1, ra: 1, ..Default::default() },
+                qdcount: 1,
+                ancount: 1,
+                questions: vec![DnsQuestion {
+                    name: DnsName { labels: vec![label(b"example"), label(b"com"), label(b"")] },
+                    qtype: 1,
+                    qclass: 1,
+                }],
+                answers: vec![DnsResourceRecord {
+                    name: DnsName { labels: vec![DnsLabel::Pointer { offset: 12 }] },
+                    type_: 1,
+                    class: 1,
+                    ttl: 300,
+                    rdlength: 4,
+                    rdata: vec![93, 184, 216, 34],
+                }],
+                ..Default::default()
+            },
+        ),
+        #[should_panic(expected = "Incomplete")]
+        case::truncated(
+            &hex!("123401000001000000000000076578616d706c65"),
+            Dns::default(),
+        ),
+    )]
+    fn test_dns_rw(input: &[u8], expected: Dns) {
+        let ret_read = Dns::try_from(input).unwrap();
+        assert_eq!(expected, ret_read);
+
+        let ret_write = LayerExt::to_bytes(&ret_read).unwrap();
+        assert_eq!(input.to_vec(), ret_write);
+    }
+
+    #[test]
+    fn test_dns_name_new() {
+        assert_eq!(
+            DnsName {
+                labels: vec![label(b"example"), label(b"com"), label(b"")]
+            },
+            DnsName::new("example.com.").unwrap()
+        );
+        assert_eq!(DnsName::default(), DnsName::new("").unwrap());
+        assert!(DnsName::new(&"a".repeat(64)).is_err());
+    }
+
+    #[rstest(input, expected,
+        case::query(
+            &hex!("123401000001000000000000076578616d706c6503636f6d0000010001"),
+            Ok(vec!["example.com".to_string()]),
+        ),
+        case::compressed(
+            &hex!("123401000002000000000000076578616d706c6503636f6d0000010001 03777777c00c00010001"),
+            Ok(vec!["example.com".to_string(), "www.example.com".to_string()]),
+        ),
+        case::root(
+            &hex!("123401000001000000000000 00 00020001"),
+            Ok(vec!["".to_string()]),
+        ),
+        case::pointer_loop(
+            &hex!("123401000001000000000000 c00c 00010001"),
+            Err(LayerError::Parse("dns name compression pointer loop".to_string())),
+        ),
+        case::label_pointer_loop(
+            &hex!("123401000001000000000000 0a61616161616161616161 c00c 00010001"),
+            Err(LayerError::Parse("dns name exceeds 255 bytes".to_string())),
+        ),
+        case::pointer_out_of_bounds(
+            &hex!("123401000001000000000000 c0ff 00010001"),
+            Err(LayerError::Parse("dns name pointer 255 out of bounds".to_string())),
+        ),
+    )]
+    fn test_dns_question_names(input: &[u8], expected: Result<Vec<String>, LayerError>) {
+        let dns = Dns::try_from(input).unwrap();
+        assert_eq!(expected, dns.question_names());
+    }
+
+    #[test]
+    fn test_dns_finalize() {
+        let mut dns = Dns {
+            id: 0x1234,
+            flags: DnsFlags {
+                rd: 1,
+                ..Default::default()
+            },
+            questions: vec![DnsQuestion {
+                name: DnsName::new("example.com").unwrap(),
+                qtype: 1,
+                qclass: 1,
+            }],
+            ..Default::default()
+        };
+        dns.finalize(&[], &[]).unwrap();
+
+        assert_eq!(
+            hex!("123401000001000000000000076578616d706c6503636f6d0000010001").to_vec(),
+            LayerExt::to_bytes(&dns).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_dns_tcp() {
+        let input = hex!("001d123401000001000000000000076578616d706c6503636f6d0000010001");
+
+        let (rest, dns_tcp) = DnsTcp::parse(&input).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(29, dns_tcp.length);
+        assert_eq!(1, dns_tcp.dns.questions.len());
+
+        let mut dns_tcp = DnsTcp {
+            length: 0,
+            ..dns_tcp
+        };
+        dns_tcp.finalize(&[], &[]).unwrap();
+        assert_eq!(input.to_vec(), LayerExt::to_bytes(&dns_tcp).unwrap());
+    }
+}
@@ -14,10 +14,25 @@ pub enum LayerError {
     Parse(String),
     /// Error during finalization
     Finalize(String),
+    /// Layer is invalid, see [LayerExt::validate](crate::layer::LayerExt::validate)
+    Validate(String),
     /// Deku Error
     DekuError(String),
 }
 
+impl LayerError {
+    /// Static string describing the kind of error, without allocating
+    pub fn kind_str(&self) -> &'static str {
+        match self {
+            LayerError::Incomplete(_) => "incomplete",
+            LayerError::Parse(_) => "parse",
+            LayerError::Finalize(_) => "finalize",
+            LayerError::Validate(_) => "validate",
+            LayerError::DekuError(_) => "deku",
+        }
+    }
+}
+
 impl From<DekuError> for LayerError {
     fn from(e: DekuError) -> Self {
         match e {
@@ -27,3 +42,20 @@ impl From<DekuError> for LayerError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest(input, expected,
+        case(LayerError::Incomplete(8), "incomplete"),
+        case(LayerError::Parse("some error".to_string()), "parse"),
+        case(LayerError::Finalize("some error".to_string()), "finalize"),
+        case(LayerError::Validate("some error".to_string()), "validate"),
+        case(LayerError::DekuError("some error".to_string()), "deku"),
+    )]
+    fn test_kind_str(input: LayerError, expected: &str) {
+        assert_eq!(expected, input.kind_str());
+    }
+}
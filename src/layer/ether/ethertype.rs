@@ -271,6 +271,16 @@ pub enum EtherType {
     Unknown(u16),
 }
 
+impl EtherType {
+    /// Numeric value of the ether type field
+    pub fn value(&self) -> u16 {
+        let data = self
+            .to_bytes()
+            .expect("dev error: writing a u16 ether type should never fail");
+        u16::from_be_bytes([data[0], data[1]])
+    }
+}
+
 impl Default for EtherType {
     fn default() -> Self {
         EtherType::IPv4
@@ -292,6 +302,12 @@ mod tests {
         assert_eq!(test_data, ret_write);
     }
 
+    #[test]
+    fn test_ethertype_value() {
+        assert_eq!(0x86DD, EtherType::IPv6.value());
+        assert_eq!(0x0026, EtherType::Unknown(0x0026).value());
+    }
+
     #[test]
     fn test_ethertype_default() {
         assert_eq!(EtherType::IPv4, EtherType::default())
@@ -7,6 +7,7 @@ Ethernet layer
 
 use crate::layer::{Layer, LayerExt};
 use alloc::{format, vec::Vec};
+use core::convert::TryFrom;
 use deku::prelude::*;
 
 mod ethertype;
@@ -51,10 +52,87 @@ pub struct Ether {
     pub ether_type: EtherType,
 }
 
+/// Largest ether type field value interpreted as an IEEE 802.3 frame length
+const IEEE8023_MAX_LENGTH: u16 = 1500;
+
+/// Ethernet preamble (7 bytes) followed by the start frame delimiter (1 byte)
+pub const PREAMBLE_SFD: [u8; 8] = [0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0xD5];
+
+/// Length of the ethernet frame check sequence
+pub const FCS_LENGTH: usize = 4;
+
+/// Minimum length of an ethernet frame, not including the frame check sequence
+///
+/// Shorter frames are padded to this length on the wire.
+pub const MIN_FRAME_LENGTH: usize = 60;
+
+/// Minimum ethernet interframe gap, in bytes
+pub const INTERFRAME_GAP_LENGTH: usize = 12;
+
+impl Ether {
+    /// Strip a leading preamble and start frame delimiter, if present
+    ///
+    /// Some capture sources, such as hardware taps, include the preamble in the frame.
+    /// This can be used before parsing a packet starting at [Ether].
+    ///
+    /// ```rust
+    /// # use hatchet::{layer::ether::Ether, packet::PacketParser};
+    /// # use hexlit::hex;
+    /// let data = hex!("55555555555555d5 feff20000100 000001000000 0800");
+    /// let (_rest, packet) = PacketParser::new()
+    ///     .parse_packet::<Ether>(Ether::strip_preamble(&data))
+    ///     .unwrap();
+    /// ```
+    pub fn strip_preamble(input: &[u8]) -> &[u8] {
+        input.strip_prefix(&PREAMBLE_SFD[..]).unwrap_or(input)
+    }
+
+    /// Parse an ethernet frame, skipping a leading preamble and start frame delimiter if present
+    ///
+    /// See [Ether::strip_preamble]
+    pub fn parse_with_preamble(input: &[u8]) -> Result<(&[u8], Self), LayerError> {
+        Self::parse(Self::strip_preamble(input))
+    }
+
+    /// Returns true if the frame is an IEEE 802.3 frame
+    ///
+    /// Per IEEE 802.3, an ether type value less than or equal to 1500 is the length of the payload
+    pub fn is_8023(&self) -> bool {
+        self.payload_length().is_some()
+    }
+
+    /// Length of the payload for IEEE 802.3 frames, `None` if the field is an ether type
+    pub fn payload_length(&self) -> Option<u16> {
+        let value = self.ether_type.value();
+        if value <= IEEE8023_MAX_LENGTH {
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
 impl Layer for Ether {}
 impl LayerExt for Ether {
-    fn finalize(&mut self, _prev: &[LayerOwned], _next: &[LayerOwned]) -> Result<(), LayerError> {
+    /// Update the payload length of IEEE 802.3 frames, the ether type is left as is otherwise
+    fn finalize(&mut self, _prev: &[LayerOwned], next: &[LayerOwned]) -> Result<(), LayerError> {
         // TODO: Maybe update the type based on the next layer?
+        if self.is_8023() {
+            let length = crate::layer::utils::length_of_layers(next)?;
+            let length = u16::try_from(length)
+                .ok()
+                .filter(|length| *length <= IEEE8023_MAX_LENGTH)
+                .ok_or_else(|| {
+                    LayerError::Finalize(format!(
+                        "802.3 payload of {} bytes exceeds {} bytes",
+                        length, IEEE8023_MAX_LENGTH
+                    ))
+                })?;
+
+            let (_rest, ether_type) = EtherType::from_bytes((&length.to_be_bytes(), 0))?;
+            self.ether_type = ether_type;
+        }
+
         Ok(())
     }
 
@@ -94,6 +172,70 @@ mod tests {
         assert_eq!(input.to_vec(), ret_write);
     }
 
+    #[rstest(input, expected_length,
+        case::ethernet2(&hex!("feff200001000000010000000800"), None),
+        case::ieee8023(&hex!("0180c2000000000000000001 0026 424203000000000080000000000000000000"), Some(38)),
+        case::ieee8023_max(&hex!("0180c2000000000000000001 05dc"), Some(1500)),
+        case::ieee8023_named(&hex!("0180c2000000000000000001 0004"), Some(4)),
+        case::min_ether_type(&hex!("0180c2000000000000000001 0600"), None),
+    )]
+    fn test_ether_8023(input: &[u8], expected_length: Option<u16>) {
+        let (_rest, ether) = Ether::parse(input).unwrap();
+
+        assert_eq!(expected_length.is_some(), ether.is_8023());
+        assert_eq!(expected_length, ether.payload_length());
+    }
+
+    #[rstest(input,
+        case::preamble(&hex!("55555555555555d5 feff200001000000010000000800 aabb")),
+        case::no_preamble(&hex!("feff200001000000010000000800 aabb")),
+    )]
+    fn test_ether_parse_with_preamble(input: &[u8]) {
+        let (rest, ether) = Ether::parse_with_preamble(input).unwrap();
+
+        assert_eq!(
+            Ether {
+                dst: MacAddress([0xfe, 0xff, 0x20, 0x00, 0x01, 0x00]),
+                src: MacAddress([0x00, 0x00, 0x01, 0x00, 0x00, 0x00]),
+                ether_type: EtherType::IPv4,
+            },
+            ether
+        );
+        assert_eq!(&hex!("aabb"), rest);
+    }
+
+    #[test]
+    fn test_ether_strip_preamble() {
+        // Partial preamble is not stripped
+        let input = hex!("555555d5 feff200001000000010000000800");
+        assert_eq!(&input[..], Ether::strip_preamble(&input));
+    }
+
+    #[rstest(
+        next_length,
+        expected,
+        case(38, Some(EtherType::Unknown(38))),
+        case(4, Some(EtherType::IEEE8023)),
+        case(1500, Some(EtherType::Unknown(1500))),
+        case(1501, None)
+    )]
+    fn test_ether_finalize_8023(next_length: usize, expected: Option<EtherType>) {
+        let mut ether = Ether {
+            ether_type: EtherType::Unknown(0),
+            ..Default::default()
+        };
+        let next: Vec<LayerOwned> = vec![Box::new(crate::layer::raw::Raw {
+            data: vec![0; next_length],
+            bit_offset: 0,
+        })];
+
+        let ret = ether.finalize(&[], &next);
+        assert_eq!(expected.is_some(), ret.is_ok());
+        if let Some(expected) = expected {
+            assert_eq!(expected, ether.ether_type);
+        }
+    }
+
     #[test]
     fn test_ether_default() {
         assert_eq!(
@@ -0,0 +1,181 @@
+/*!
+GRE layer
+
+Generic Routing Encapsulation, [RFC2784](https://datatracker.ietf.org/doc/html/rfc2784) with
+the key and sequence number extensions of [RFC2890](https://datatracker.ietf.org/doc/html/rfc2890)
+*/
+
+use crate::layer::ether::EtherType;
+use crate::layer::ip::Checksum;
+use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
+use alloc::{format, vec::Vec};
+use deku::prelude::*;
+
+/**
+GRE Header
+
+```text
+ 0                   1                   2                   3
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|C| |K|S| Reserved0       | Ver |         Protocol Type         |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|      Checksum (optional)      |       Reserved1 (Optional)    |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|                         Key (optional)                        |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|                 Sequence Number (Optional)                    |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+```
+
+The presence flags are updated from the optional fields on [finalize](LayerExt::finalize).
+*/
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(endian = "big")]
+pub struct Gre {
+    /// Checksum Present
+    #[deku(bits = "1", update = "self.checksum.is_some()")]
+    pub checksum_present: bool,
+    /// Reserved, formerly Routing Present
+    #[deku(bits = "1")]
+    pub routing_present: bool,
+    /// Key Present
+    #[deku(bits = "1", update = "self.key.is_some()")]
+    pub key_present: bool,
+    /// Sequence Number Present
+    #[deku(bits = "1", update = "self.sequence.is_some()")]
+    pub sequence_present: bool,
+    /// Reserved
+    #[deku(bits = "9")]
+    pub reserved0: u16,
+    /// Version
+    #[deku(bits = "3")]
+    pub version: u8,
+    /// Protocol type of the payload
+    pub protocol: EtherType,
+    /// Checksum of the header and payload
+    #[deku(cond = "*checksum_present")]
+    pub checksum: Option<u16>,
+    /// Reserved, present with the checksum
+    #[deku(cond = "*checksum_present")]
+    pub reserved1: Option<u16>,
+    /// Key, identifying a flow within the tunnel
+    #[deku(cond = "*key_present")]
+    pub key: Option<u32>,
+    /// Sequence Number
+    #[deku(cond = "*sequence_present")]
+    pub sequence: Option<u32>,
+}
+
+impl Default for Gre {
+    fn default() -> Self {
+        Gre {
+            checksum_present: false,
+            routing_present: false,
+            key_present: false,
+            sequence_present: false,
+            reserved0: 0,
+            version: 0,
+            protocol: EtherType::IPv4,
+            checksum: None,
+            reserved1: None,
+            key: None,
+            sequence: None,
+        }
+    }
+}
+
+impl Layer for Gre {}
+impl LayerExt for Gre {
+    /// Update the presence flags and the checksum, if present
+    fn finalize(&mut self, _prev: &[LayerOwned], next: &[LayerOwned]) -> Result<(), LayerError> {
+        self.update()?;
+
+        if self.checksum.is_some() {
+            self.checksum = Some(0);
+            self.reserved1 = Some(self.reserved1.unwrap_or(0));
+
+            let mut checksum = Checksum::new();
+            checksum.add_bytes(&LayerExt::to_bytes(self)?);
+            for layer in next {
+                checksum.add_bytes(&layer.to_bytes()?);
+            }
+
+            self.checksum = Some(checksum.finish());
+        }
+
+        Ok(())
+    }
+
+    fn parse(input: &[u8]) -> Result<(&[u8], Self), LayerError>
+    where
+        Self: Sized,
+    {
+        let ((rest, bit_offset), gre) = Gre::from_bytes((input, 0))?;
+        debug_assert_eq!(0, bit_offset);
+        Ok((rest, gre))
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
+        Ok(DekuContainerWrite::to_bytes(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::raw::Raw;
+    use alloc::{boxed::Box, vec};
+    use hexlit::hex;
+    use rstest::*;
+    use std::convert::TryFrom;
+
+    #[rstest(input, expected,
+        case(&hex!("00000800"), Gre::default()),
+        case(&hex!("2000 86dd 0000007b"), Gre {
+            key_present: true,
+            protocol: EtherType::IPv6,
+            key: Some(123),
+            ..Default::default()
+        }),
+        case(&hex!("b000 0800 f7ff0000 0000007b 00000001"), Gre {
+            checksum_present: true,
+            key_present: true,
+            sequence_present: true,
+            checksum: Some(0xf7ff),
+            reserved1: Some(0),
+            key: Some(123),
+            sequence: Some(1),
+            ..Default::default()
+        }),
+    )]
+    fn test_gre_rw(input: &[u8], expected: Gre) {
+        let ret_read = Gre::try_from(input).unwrap();
+        assert_eq!(expected, ret_read);
+
+        let ret_write = LayerExt::to_bytes(&ret_read).unwrap();
+        assert_eq!(input.to_vec(), ret_write);
+    }
+
+    #[test]
+    fn test_gre_finalize() {
+        let mut gre = Gre {
+            checksum: Some(0),
+            key: Some(123),
+            ..Default::default()
+        };
+        let next: Vec<LayerOwned> = vec![Box::new(Raw {
+            data: hex!("aabb").to_vec(),
+            bit_offset: 0,
+        })];
+        gre.finalize(&[], &next).unwrap();
+
+        assert!(gre.checksum_present);
+        assert!(gre.key_present);
+        assert!(!gre.sequence_present);
+        assert_eq!(
+            hex!("a000 0800 acc8 0000 0000007b").to_vec(),
+            LayerExt::to_bytes(&gre).unwrap()
+        );
+    }
+}
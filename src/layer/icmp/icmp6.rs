@@ -0,0 +1,246 @@
+use crate::get_layer;
+use crate::layer::ip::{Checksum, IpProtocol, Ipv6, Ipv6PseudoHeader};
+use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
+use alloc::{format, string::ToString, vec::Vec};
+use core::convert::TryFrom;
+use deku::prelude::*;
+
+/// Icmp6 Type
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(
+    type = "u8",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "deku::ctx::Endian::Big",
+    endian = "endian"
+)]
+#[non_exhaustive]
+pub enum Icmp6Type {
+    /// Destination Unreachable
+    #[deku(id = "1")]
+    DestUnreach,
+    /// Packet Too Big
+    #[deku(id = "2")]
+    PacketTooBig,
+    /// Time Exceeded
+    #[deku(id = "3")]
+    TimeExceeded,
+    /// Parameter Problem
+    #[deku(id = "4")]
+    ParameterProblem,
+    /// Echo Request
+    #[deku(id = "128")]
+    EchoRequest,
+    /// Echo Reply
+    #[deku(id = "129")]
+    EchoReply,
+    /// Router Solicitation
+    #[deku(id = "133")]
+    RouterSolicitation,
+    /// Router Advertisement
+    #[deku(id = "134")]
+    RouterAdvertisement,
+    /// Neighbor Solicitation
+    #[deku(id = "135")]
+    NeighborSolicitation,
+    /// Neighbor Advertisement
+    #[deku(id = "136")]
+    NeighborAdvertisement,
+    /// Redirect
+    #[deku(id = "137")]
+    Redirect,
+
+    /// Unknown Icmp6 Type
+    #[deku(id_pat = "_")]
+    Unknown(u8),
+}
+
+/**
+ICMPv6 Header
+
+```text
+ 0                   1                   2                   3
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|      Type     |      Code     |            Checksum           |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|                            Message                            |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|                             Data                              |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+```
+*/
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(endian = "big")]
+pub struct Icmp6 {
+    /// ICMPv6 Type
+    pub icmp_type: Icmp6Type,
+    /// ICMPv6 Subtype
+    pub code: u8,
+    /// Checksum
+    pub checksum: u16,
+    /// Message
+    pub message: u32,
+    /// Data
+    #[deku(count = "deku::rest.len() / 8")]
+    pub data: Vec<u8>,
+}
+
+impl Icmp6 {
+    /// Create an Echo Request
+    ///
+    /// The identifier and sequence number are packed into the `message` field
+    pub fn echo_request(id: u16, seq: u16, data: Vec<u8>) -> Self {
+        Icmp6 {
+            icmp_type: Icmp6Type::EchoRequest,
+            message: (u32::from(id) << 16) | u32::from(seq),
+            data,
+            ..Default::default()
+        }
+    }
+
+    /// Create an Echo Reply
+    ///
+    /// The identifier and sequence number are packed into the `message` field
+    pub fn echo_reply(id: u16, seq: u16, data: Vec<u8>) -> Self {
+        Icmp6 {
+            icmp_type: Icmp6Type::EchoReply,
+            message: (u32::from(id) << 16) | u32::from(seq),
+            data,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for Icmp6 {
+    fn default() -> Self {
+        Icmp6 {
+            icmp_type: Icmp6Type::EchoReply,
+            code: 0,
+            checksum: 0,
+            message: 0,
+            data: Vec::new(),
+        }
+    }
+}
+
+impl Layer for Icmp6 {}
+impl LayerExt for Icmp6 {
+    /// Update the checksum
+    ///
+    /// Unlike [Icmp4](super::Icmp4), the checksum covers the [Ipv6] pseudo-header of the
+    /// previous layer. It is left as-is if the previous layer is not [Ipv6].
+    fn finalize(&mut self, prev: &[LayerOwned], next: &[LayerOwned]) -> Result<(), LayerError> {
+        let ipv6 = match prev.last().and_then(|layer| get_layer!(layer, Ipv6)) {
+            Some(ipv6) => ipv6,
+            None => return Ok(()),
+        };
+
+        let icmp_header = {
+            let mut data = LayerExt::to_bytes(self)?;
+
+            // Clear checksum bytes for calculation
+            data[2] = 0x00;
+            data[3] = 0x00;
+
+            data
+        };
+
+        let mut icmp_checksum = Checksum::new();
+        icmp_checksum.add_bytes(&icmp_header);
+
+        let mut icmp_length = icmp_header.len();
+        for layer in next {
+            let data = layer.to_bytes()?;
+            icmp_length += data.len();
+            icmp_checksum.add_bytes(&data);
+        }
+
+        let icmp_length = u32::try_from(icmp_length).map_err(|_e| {
+            LayerError::Finalize("Failed to convert icmp6 length to u32".to_string())
+        })?;
+
+        let mut checksum = Checksum::new();
+        checksum
+            .add_bytes(&Ipv6PseudoHeader::new(ipv6, IpProtocol::IPV6ICMP, icmp_length).to_bytes()?);
+        checksum.merge(&icmp_checksum);
+
+        self.checksum = checksum.finish();
+
+        Ok(())
+    }
+
+    fn parse(input: &[u8]) -> Result<(&[u8], Self), LayerError>
+    where
+        Self: Sized,
+    {
+        let ((rest, bit_offset), icmp) = Icmp6::from_bytes((input, 0))?;
+        debug_assert_eq!(0, bit_offset);
+        Ok((rest, icmp))
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
+        Ok(DekuContainerWrite::to_bytes(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use hexlit::hex;
+    use rstest::*;
+
+    fn ipv6(src: u128, dst: u128) -> LayerOwned {
+        Box::new(Ipv6 {
+            src,
+            dst,
+            ..Default::default()
+        })
+    }
+
+    #[rstest(input, expected,
+        case(
+            &hex!("8000c2c712340001aabb"),
+            Icmp6 {
+                icmp_type: Icmp6Type::EchoRequest,
+                code: 0,
+                checksum: 0xc2c7,
+                message: 0x12340001,
+                data: vec![0xaa, 0xbb],
+            },
+        ),
+        case(
+            &hex!("fe000000 00000000"),
+            Icmp6 {
+                icmp_type: Icmp6Type::Unknown(254),
+                ..Default::default()
+            },
+        ),
+    )]
+    fn test_icmp6_rw(input: &[u8], expected: Icmp6) {
+        let ret_read = Icmp6::try_from(input).unwrap();
+        assert_eq!(expected, ret_read);
+
+        let ret_write = LayerExt::to_bytes(&ret_read).unwrap();
+        assert_eq!(input.to_vec(), ret_write);
+    }
+
+    #[test]
+    fn test_icmp6_finalize_pseudo_header() {
+        let mut icmp = Icmp6::echo_request(0x1234, 0x0001, vec![]);
+
+        icmp.finalize(&[ipv6(1, 2)], &[]).unwrap();
+        assert_eq!(0x6d85, icmp.checksum);
+
+        // the checksum depends on the ipv6 addresses
+        icmp.finalize(&[ipv6(1, 3)], &[]).unwrap();
+        assert_eq!(0x6d84, icmp.checksum);
+    }
+
+    #[test]
+    fn test_icmp6_finalize_no_ipv6() {
+        let mut icmp = Icmp6::echo_request(0x1234, 0x0001, vec![]);
+        icmp.finalize(&[], &[]).unwrap();
+        assert_eq!(0, icmp.checksum);
+    }
+}
@@ -1,13 +1,18 @@
 /*!
-ICMP layer
+ICMP layers
+
+[Icmp4] and [Icmp6] differ in their checksum: the ICMPv4 checksum covers only the icmp
+message, while the ICMPv6 checksum also covers the [Ipv6](crate::layer::ip::Ipv6) pseudo-header.
 */
 
 use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
 use alloc::{format, vec::Vec};
 use deku::prelude::*;
 
+mod icmp6;
 mod icmp_type;
 
+pub use icmp6::{Icmp6, Icmp6Type};
 pub use icmp_type::IcmpType;
 
 /**
@@ -41,6 +46,32 @@ pub struct Icmp4 {
     pub data: Vec<u8>,
 }
 
+impl Icmp4 {
+    /// Create an Echo Request
+    ///
+    /// The identifier and sequence number are packed into the `message` field
+    pub fn echo_request(id: u16, seq: u16, data: Vec<u8>) -> Self {
+        Icmp4 {
+            icmp_type: IcmpType::EchoRequest,
+            message: (u32::from(id) << 16) | u32::from(seq),
+            data,
+            ..Default::default()
+        }
+    }
+
+    /// Create an Echo Reply
+    ///
+    /// The identifier and sequence number are packed into the `message` field
+    pub fn echo_reply(id: u16, seq: u16, data: Vec<u8>) -> Self {
+        Icmp4 {
+            icmp_type: IcmpType::EchoReply,
+            message: (u32::from(id) << 16) | u32::from(seq),
+            data,
+            ..Default::default()
+        }
+    }
+}
+
 impl Default for Icmp4 {
     fn default() -> Self {
         Icmp4 {
@@ -55,6 +86,10 @@ impl Default for Icmp4 {
 
 impl Layer for Icmp4 {}
 impl LayerExt for Icmp4 {
+    /// Update the checksum
+    ///
+    /// Unlike [Icmp6], the checksum covers only the icmp message, the previous layers are
+    /// not part of the checksum.
     fn finalize(&mut self, _prev: &[LayerOwned], _next: &[LayerOwned]) -> Result<(), LayerError> {
         let icmp_header = {
             let mut data = LayerExt::to_bytes(self)?;
@@ -88,6 +123,7 @@ impl LayerExt for Icmp4 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layer::ip::Ipv4;
     use hexlit::hex;
     use rstest::*;
     use std::convert::TryFrom;
@@ -126,6 +162,28 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_icmp_echo_request() {
+        let icmp = Icmp4::echo_request(0x5f56, 0x0001, vec![0xAA, 0xBB]);
+        assert_eq!(
+            Icmp4 {
+                icmp_type: IcmpType::EchoRequest,
+                code: 0,
+                checksum: 0,
+                message: 0x5f560001,
+                data: vec![0xAA, 0xBB],
+            },
+            icmp
+        );
+    }
+
+    #[test]
+    fn test_icmp_echo_reply() {
+        let icmp = Icmp4::echo_reply(0xFFFF, 0xFFFE, vec![]);
+        assert_eq!(IcmpType::EchoReply, icmp.icmp_type);
+        assert_eq!(0xFFFFFFFE, icmp.message);
+    }
+
     #[test]
     fn test_icmp_finalize_checksum() {
         let expected_checksum = 0xFFFF;
@@ -137,6 +195,26 @@ mod tests {
         assert_eq!(expected_checksum, icmp.checksum);
     }
 
+    #[test]
+    fn test_icmp_finalize_ignores_ip() {
+        let mut icmp = Icmp4::echo_request(0x1234, 0x0001, vec![]);
+        icmp.finalize(&[], &[]).unwrap();
+        let expected_checksum = icmp.checksum;
+
+        for ipv4 in [
+            Ipv4::default(),
+            Ipv4 {
+                src: 0xc0a80001,
+                dst: 0xc0a80002,
+                ..Default::default()
+            },
+        ] {
+            let prev: Vec<LayerOwned> = vec![Box::new(ipv4)];
+            icmp.finalize(&prev, &[]).unwrap();
+            assert_eq!(expected_checksum, icmp.checksum);
+        }
+    }
+
     #[test]
     fn test_icmp_finalize() {
         let mut icmp = Icmp4::default();
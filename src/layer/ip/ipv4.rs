@@ -1,13 +1,13 @@
 /*!
   Ipv4
 */
-use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
+use crate::layer::{Layer, LayerError, LayerExt, LayerOwned, LengthField};
 
 use super::IpProtocol;
 use alloc::string::ToString;
-use alloc::{format, vec, vec::Vec};
+use alloc::{boxed::Box, format, vec, vec::Vec};
 use core::convert::TryFrom;
-use deku::bitvec::{BitSlice, Msb0};
+use deku::bitvec::{BitSlice, BitView, Msb0};
 use deku::prelude::*;
 
 /// Ipv4 option class
@@ -100,7 +100,7 @@ Ipv4 Header
 ```
 */
 #[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
-#[deku(endian = "big")]
+#[deku(endian = "big", ctx = "lenient: bool", ctx_default = "false")]
 pub struct Ipv4 {
     /// Version
     #[deku(bits = "4")]
@@ -115,6 +115,9 @@ pub struct Ipv4 {
     #[deku(bits = "2")]
     pub ecn: u8,
     /// Total Length
+    ///
+    /// Parsing does not rely on this field, the remaining data is the payload. This handles
+    /// captures with TCP Segmentation Offload where the length is 0, filled in by the NIC.
     pub length: u16,
     /// Identification
     pub identification: u16,
@@ -135,15 +138,36 @@ pub struct Ipv4 {
     /// Destination IP Address
     pub dst: u32,
     /// List of ipv4 options
-    #[deku(reader = "Ipv4::read_options(*ihl, deku::rest)")]
+    #[deku(reader = "Ipv4::read_options(*ihl, lenient, deku::rest)")]
     pub options: Vec<Ipv4Option>,
+    /// Option bytes which could not be parsed, see [Ipv4::parse_lenient]
+    #[deku(reader = "Ipv4::read_options_raw(*ihl, lenient, deku::byte_offset, deku::rest)")]
+    pub options_raw: Vec<u8>,
 }
 
 impl Ipv4 {
+    /// Parse an ipv4 layer, storing unparseable options as raw bytes
+    ///
+    /// On an option which can't be parsed, the remaining option bytes are stored in
+    /// `options_raw` instead of returning an error, such that `to_bytes` reproduces the input.
+    pub fn parse_lenient(input: &[u8]) -> Result<(&[u8], Self), LayerError> {
+        let (rest, ipv4) = Ipv4::read(input.view_bits::<Msb0>(), true)?;
+        debug_assert_eq!(0, rest.len() % 8);
+        Ok((&input[input.len() - rest.len() / 8..], ipv4))
+    }
+
+    /// Parse an ipv4 layer leniently, see [Ipv4::parse_lenient]
+    ///
+    /// Returns a dyn Layer, to be used in [PacketParser](crate::packet::PacketParser) bindings
+    pub fn parse_layer_lenient(input: &[u8]) -> Result<(&[u8], Box<dyn LayerExt>), LayerError> {
+        Ipv4::parse_lenient(input).map(|(rest, ipv4)| (rest, Box::new(ipv4) as Box<dyn LayerExt>))
+    }
+
     /// Read all ipv4 options
     fn read_options(
         ihl: u8, // number of 32 bit words
-        rest: &BitSlice<Msb0, u8>,
+        lenient: bool,
+        input: &BitSlice<Msb0, u8>,
     ) -> Result<(&BitSlice<Msb0, u8>, Vec<Ipv4Option>), DekuError> {
         if ihl > 5 {
             // we have options to parse
@@ -152,18 +176,25 @@ impl Ipv4 {
             let bits = (ihl as usize - 5) * 32;
 
             // Check split_at precondition
-            if bits > rest.len() {
+            if bits > input.len() {
                 return Err(DekuError::Parse(
                     "not enough data to read ipv4 options".to_string(),
                 ));
             }
 
-            let (mut option_rest, rest) = rest.split_at(bits);
+            let (mut option_rest, rest) = input.split_at(bits);
 
             let mut ipv4_options = Vec::with_capacity(1); // at-least 1
             while !option_rest.is_empty() {
                 let (option_rest_new, tcp_option) =
-                    Ipv4Option::read(option_rest, deku::ctx::Endian::Big)?;
+                    match Ipv4Option::read(option_rest, deku::ctx::Endian::Big) {
+                        Ok(v) => v,
+                        // leave the unparsed option bytes to `read_options_raw`
+                        Err(_e) if lenient => {
+                            return Ok((&input[bits - option_rest.len()..], ipv4_options))
+                        }
+                        Err(e) => return Err(e),
+                    };
 
                 ipv4_options.push(tcp_option);
 
@@ -172,10 +203,99 @@ impl Ipv4 {
 
             Ok((rest, ipv4_options))
         } else {
-            Ok((rest, vec![]))
+            Ok((input, vec![]))
         }
     }
 
+    /// Read the option bytes left unparsed by `read_options`
+    ///
+    /// `byte_offset` is the number of header bytes read so far, the remaining option bytes
+    /// extend up to the internet header length. Only lenient parsing leaves option bytes
+    /// unparsed.
+    fn read_options_raw(
+        ihl: u8,
+        lenient: bool,
+        byte_offset: usize,
+        rest: &BitSlice<Msb0, u8>,
+    ) -> Result<(&BitSlice<Msb0, u8>, Vec<u8>), DekuError> {
+        if !lenient {
+            return Ok((rest, Vec::new()));
+        }
+
+        let length = (ihl as usize).saturating_mul(4).saturating_sub(byte_offset);
+
+        Vec::<u8>::read(rest, (deku::ctx::Limit::new_count(length), ()))
+    }
+
+    /// Validate the ipv4 options
+    ///
+    /// Checks that:
+    /// - The length of each option is consistent with its value
+    /// - End of Option List is only followed by padding (additional End of Option List)
+    /// - The total length of the options matches the internet header length
+    pub fn validate_options(&self) -> Result<(), LayerError> {
+        let mut options_len = 0usize;
+        let mut end_of_options = false;
+
+        for option in self.options.iter() {
+            let option_len = match &option.option {
+                Ipv4OptionType::EOOL => {
+                    end_of_options = true;
+                    1
+                }
+                Ipv4OptionType::NOP => 1,
+                Ipv4OptionType::Unknown {
+                    type_,
+                    length,
+                    value,
+                } => {
+                    if usize::from(*length) != value.len() + 2 {
+                        return Err(LayerError::Validate(format!(
+                            "ipv4 option {} length {} does not match value length {}",
+                            type_,
+                            length,
+                            value.len()
+                        )));
+                    }
+                    usize::from(*length)
+                }
+            };
+
+            if end_of_options && option.option != Ipv4OptionType::EOOL {
+                return Err(LayerError::Validate(
+                    "ipv4 option found after end of option list".to_string(),
+                ));
+            }
+
+            options_len += option_len;
+        }
+
+        if !self.options_raw.is_empty() {
+            return Err(LayerError::Validate(format!(
+                "ipv4 options contain {} unparsed bytes",
+                self.options_raw.len()
+            )));
+        }
+
+        let expected_len = usize::from(self.ihl)
+            .checked_sub(5)
+            .ok_or_else(|| LayerError::Validate(format!("invalid ipv4 ihl {}", self.ihl)))?
+            * 4;
+        if options_len != expected_len {
+            return Err(LayerError::Validate(format!(
+                "ipv4 options length {} does not match ihl length {}",
+                options_len, expected_len
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the Don't Fragment flag is set
+    pub fn dont_fragment(&self) -> bool {
+        self.flags & 0b010 != 0
+    }
+
     /// Update the checksum field
     pub fn update_checksum(&mut self) -> Result<(), LayerError> {
         let mut ipv4 = LayerExt::to_bytes(self)?;
@@ -190,6 +310,33 @@ impl Ipv4 {
     }
 }
 
+/// Generator of incrementing ipv4 identification values, wrapping around at [u16::MAX]
+///
+/// Useful to assign unique identifications when crafting a sequence of packets
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Ipv4IdCounter {
+    next: u16,
+}
+
+impl Ipv4IdCounter {
+    /// Create a counter starting at `start`
+    pub fn new(start: u16) -> Self {
+        Self { next: start }
+    }
+
+    /// Return the next identification value
+    pub fn next_id(&mut self) -> u16 {
+        let id = self.next;
+        self.next = self.next.wrapping_add(1);
+        id
+    }
+
+    /// Assign the next identification value to `ipv4`
+    pub fn assign(&mut self, ipv4: &mut Ipv4) {
+        ipv4.identification = self.next_id();
+    }
+}
+
 impl Default for Ipv4 {
     fn default() -> Self {
         Ipv4 {
@@ -207,6 +354,7 @@ impl Default for Ipv4 {
             src: 0x7F000001,
             dst: 0x7F000001,
             options: vec![],
+            options_raw: vec![],
         }
     }
 }
@@ -244,6 +392,34 @@ impl LayerExt for Ipv4 {
     fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
         Ok(DekuContainerWrite::to_bytes(self)?)
     }
+
+    fn length_field(&self) -> Option<LengthField> {
+        Some(LengthField {
+            name: "total length",
+            value: self.length,
+            includes_header: true,
+            extensions_length: 0,
+        })
+    }
+
+    /// Validate the version, internet header length and options
+    fn validate(&self) -> Result<(), LayerError> {
+        if self.version != 4 {
+            return Err(LayerError::Validate(format!(
+                "invalid ipv4 version {}",
+                self.version
+            )));
+        }
+
+        if self.ihl < 5 {
+            return Err(LayerError::Validate(format!(
+                "invalid ipv4 ihl {}",
+                self.ihl
+            )));
+        }
+
+        self.validate_options()
+    }
 }
 
 #[cfg(test)]
@@ -313,6 +489,7 @@ mod tests {
                 src: 0x91FEA0ED,
                 dst: 0x91FD02CB,
                 options: vec![],
+                options_raw: vec![],
             },
         ),
 
@@ -339,6 +516,7 @@ mod tests {
                         option: Ipv4OptionType::Unknown { type_: 6, length: 40, value: vec![0, 0, 0, 1, 1, 34, 0, 1, 174, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1] }
                     }
                 ],
+                options_raw: vec![],
             },
         ),
     )]
@@ -350,6 +528,51 @@ mod tests {
         assert_eq!(input.to_vec(), ret_write);
     }
 
+    #[rstest(ipv4, expected,
+        case::valid(Ipv4::default(), Ok(())),
+        case::version(Ipv4 { version: 6, ..Default::default() },
+            Err(LayerError::Validate("invalid ipv4 version 6".to_string()))),
+        case::ihl(Ipv4 { ihl: 4, ..Default::default() },
+            Err(LayerError::Validate("invalid ipv4 ihl 4".to_string()))),
+        case::options(Ipv4 { ihl: 6, ..Default::default() },
+            Err(LayerError::Validate("ipv4 options length 0 does not match ihl length 4".to_string()))),
+    )]
+    fn test_ipv4_validate(ipv4: Ipv4, expected: Result<(), LayerError>) {
+        assert_eq!(expected, ipv4.validate());
+    }
+
+    #[rstest(input, expected_options, expected_raw,
+        case::valid(&hex!("4600003200010000400670bc7f0000017f00000101010101"), vec![Ipv4Option { copied: 0, class: Ipv4OptionClass::Control, option: Ipv4OptionType::NOP }; 4], vec![]),
+        case::truncated_option(&hex!("4600003200010000400670bc7f0000017f00000101070a00"), vec![Ipv4Option { copied: 0, class: Ipv4OptionClass::Control, option: Ipv4OptionType::NOP }], vec![0x07, 0x0a, 0x00]),
+    )]
+    fn test_ipv4_parse_lenient(
+        input: &[u8],
+        expected_options: Vec<Ipv4Option>,
+        expected_raw: Vec<u8>,
+    ) {
+        let (rest, ipv4) = Ipv4::parse_lenient(input).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(expected_options, ipv4.options);
+        assert_eq!(expected_raw, ipv4.options_raw);
+
+        // round-trip
+        assert_eq!(input, LayerExt::to_bytes(&ipv4).unwrap());
+    }
+
+    #[test]
+    fn test_ipv4_parse_strict_malformed_option() {
+        let input = hex!("4600003200010000400670bc7f0000017f00000101070a00");
+        assert!(<Ipv4 as LayerExt>::parse(&input).is_err());
+
+        let (_rest, ipv4) = Ipv4::parse_lenient(&input).unwrap();
+        assert_eq!(
+            Err(LayerError::Validate(
+                "ipv4 options contain 3 unparsed bytes".to_string()
+            )),
+            ipv4.validate_options()
+        );
+    }
+
     #[test]
     fn test_ipv4_default() {
         assert_eq!(
@@ -368,11 +591,75 @@ mod tests {
                 src: 0x7F000001,
                 dst: 0x7F000001,
                 options: vec![],
+                options_raw: vec![],
             },
             Ipv4::default()
         );
     }
 
+    #[rstest(input,
+        case::no_options(&hex!("4500004b0f490000801163a591fea0ed91fd02cb")),
+        case::option(&hex!("4f00007c000040004001fd307f0000017f00000186280000000101220001ae0000000000000000000000000000000000000000000000000000000001")),
+        case::padding(&hex!("460000180000400040010000 7f000001 7f000001 01010000")),
+    )]
+    fn test_ipv4_validate_options(input: &[u8]) {
+        let ipv4 = Ipv4::try_from(input).unwrap();
+        ipv4.validate_options().unwrap();
+    }
+
+    #[rstest(ihl, options, expected,
+        case::length_inconsistent(6, vec![
+            Ipv4Option {
+                copied: 0,
+                class: Ipv4OptionClass::Control,
+                option: Ipv4OptionType::Unknown { type_: 7, length: 10, value: vec![0, 0] },
+            },
+        ], "ipv4 option 7 length 10 does not match value length 2"),
+        case::option_after_end(6, vec![
+            Ipv4Option { copied: 0, class: Ipv4OptionClass::Control, option: Ipv4OptionType::EOOL },
+            Ipv4Option { copied: 0, class: Ipv4OptionClass::Control, option: Ipv4OptionType::NOP },
+            Ipv4Option { copied: 0, class: Ipv4OptionClass::Control, option: Ipv4OptionType::EOOL },
+            Ipv4Option { copied: 0, class: Ipv4OptionClass::Control, option: Ipv4OptionType::EOOL },
+        ], "ipv4 option found after end of option list"),
+        case::ihl_mismatch(7, vec![
+            Ipv4Option { copied: 0, class: Ipv4OptionClass::Control, option: Ipv4OptionType::NOP },
+            Ipv4Option { copied: 0, class: Ipv4OptionClass::Control, option: Ipv4OptionType::NOP },
+            Ipv4Option { copied: 0, class: Ipv4OptionClass::Control, option: Ipv4OptionType::NOP },
+            Ipv4Option { copied: 0, class: Ipv4OptionClass::Control, option: Ipv4OptionType::EOOL },
+        ], "ipv4 options length 4 does not match ihl length 8"),
+        case::invalid_ihl(4, vec![], "invalid ipv4 ihl 4"),
+    )]
+    fn test_ipv4_validate_options_invalid(ihl: u8, options: Vec<Ipv4Option>, expected: &str) {
+        let ipv4 = Ipv4 {
+            ihl,
+            options,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Err(LayerError::Validate(expected.to_string())),
+            ipv4.validate_options()
+        );
+    }
+
+    #[test]
+    fn test_ipv4_id_counter() {
+        let mut counter = Ipv4IdCounter::new(0x1000);
+
+        let ids: Vec<u16> = (0..3)
+            .map(|_| {
+                let mut ipv4 = Ipv4::default();
+                counter.assign(&mut ipv4);
+                ipv4.identification
+            })
+            .collect();
+        assert_eq!(vec![0x1000, 0x1001, 0x1002], ids);
+
+        let mut counter = Ipv4IdCounter::new(u16::MAX);
+        assert_eq!(u16::MAX, counter.next_id());
+        assert_eq!(0, counter.next_id());
+    }
+
     #[test]
     fn test_ipv4_checksum_update() {
         let expected_checksum = 0x9010;
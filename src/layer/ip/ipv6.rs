@@ -1,15 +1,152 @@
 /*!
   Ipv6
 
-  TODO: Ipv6 extension headers
+  TODO: Ipv6 extension headers other than Routing
 */
 
 use super::IpProtocol;
-use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
+use crate::layer::{Layer, LayerError, LayerExt, LayerOwned, LengthField};
 use alloc::{format, string::ToString, vec::Vec};
 use core::convert::TryFrom;
+use deku::bitvec::{BitSlice, BitVec, Msb0};
 use deku::prelude::*;
 
+/// Maximum number of extension headers parsed
+///
+/// Chaining many extension headers can be used to evade inspection or exhaust resources,
+/// parsing errors beyond this number of extension headers.
+///
+/// The limit is fixed rather than a [PacketParser](crate::packet::PacketParser) setting as
+/// layers are parsed through [LayerExt::parse], without access to the parser. RFC 8200
+/// recommends each extension header to occur at most once, except the destination options
+/// header occurring at most twice, such that a conforming packet has at most 7 extension
+/// headers: 8 is never reached by conforming packets.
+pub const MAX_IPV6_EXTENSIONS: usize = 8;
+
+/// Ipv6 routing header type specific data
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(
+    ctx = "endian: deku::ctx::Endian, routing_type: u8, length: u8",
+    id = "routing_type",
+    endian = "endian"
+)]
+pub enum Ipv6RoutingData {
+    /// Type 0 (Deprecated) [RFC5095](https://datatracker.ietf.org/doc/html/rfc5095)
+    #[deku(id = "0")]
+    Type0 {
+        /// Reserved
+        reserved: u32,
+        /// List of addresses
+        #[deku(
+            count = "if length % 2 == 0 { length / 2 } else { return Err(DekuError::Parse(\"odd length of ipv6 type 0 routing header\".to_string())) }"
+        )]
+        addresses: Vec<u128>,
+    },
+    /// Segment Routing Header [RFC8754](https://datatracker.ietf.org/doc/html/rfc8754)
+    #[deku(id = "4")]
+    Srh {
+        /// Index of the last element of the segment list
+        last_entry: u8,
+        /// Flags
+        flags: u8,
+        /// Tag
+        tag: u16,
+        /// Segment list, encoded in reverse order of the path
+        #[deku(count = "usize::from(*last_entry) + 1")]
+        segments: Vec<u128>,
+        /// Optional TLVs following the segment list
+        #[deku(
+            count = "(usize::from(length) * 8).checked_sub((usize::from(*last_entry) + 1) * 16).ok_or_else(|| DekuError::Parse(\"overflow when parsing ipv6 segment routing header\".to_string()))?"
+        )]
+        tlvs: Vec<u8>,
+    },
+    /// Unknown routing type
+    #[deku(id_pat = "_")]
+    Unknown {
+        /// Type specific data
+        #[deku(count = "usize::from(length) * 8 + 4")]
+        value: Vec<u8>,
+    },
+}
+
+/**
+Ipv6 Routing Header
+
+```text
+ 0                   1                   2                   3
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|  Next Header  |  Hdr Ext Len  |  Routing Type | Segments Left |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|                                                               |
+.                                                               .
+.                       type-specific data                      .
+.                                                               .
+|                                                               |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+```
+*/
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(ctx = "endian: deku::ctx::Endian", endian = "endian")]
+pub struct Ipv6Routing {
+    /// Next Header
+    pub next_header: IpProtocol,
+    /// Length of the header in 8-octet units, not including the first 8 octets
+    pub length: u8,
+    /// Routing Type
+    pub routing_type: u8,
+    /// Segments Left
+    pub segments_left: u8,
+    /// Routing type specific data
+    #[deku(ctx = "*routing_type, *length")]
+    pub data: Ipv6RoutingData,
+}
+
+/// Ipv6 extension header
+#[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
+pub enum Ipv6Extension {
+    /// Routing Header
+    Routing(Ipv6Routing),
+}
+
+impl Ipv6Extension {
+    /// Next Header of the extension header
+    pub fn next_header(&self) -> IpProtocol {
+        match self {
+            Ipv6Extension::Routing(routing) => routing.next_header,
+        }
+    }
+
+    /// Length in bytes of the serialized extension header
+    pub fn length(&self) -> usize {
+        match self {
+            Ipv6Extension::Routing(routing) => {
+                let data_length = match &routing.data {
+                    Ipv6RoutingData::Type0 { addresses, .. } => 4 + addresses.len() * 16,
+                    Ipv6RoutingData::Srh { segments, tlvs, .. } => {
+                        4 + segments.len() * 16 + tlvs.len()
+                    }
+                    Ipv6RoutingData::Unknown { value } => value.len(),
+                };
+                4 + data_length
+            }
+        }
+    }
+}
+
+impl DekuWrite<deku::ctx::Endian> for Ipv6Extension {
+    fn write(
+        &self,
+        output: &mut BitVec<Msb0, u8>,
+        endian: deku::ctx::Endian,
+    ) -> Result<(), DekuError> {
+        match self {
+            Ipv6Extension::Routing(routing) => routing.write(output, endian),
+        }
+    }
+}
+
 /**
 IPv6 Header
 
@@ -64,6 +201,74 @@ pub struct Ipv6 {
     pub src: u128,
     /// Destination IP Address
     pub dst: u128,
+    /// List of ipv6 extension headers, at most [MAX_IPV6_EXTENSIONS] are parsed
+    #[deku(reader = "Ipv6::read_extensions(*next_header, deku::rest)")]
+    pub extensions: Vec<Ipv6Extension>,
+}
+
+impl Ipv6 {
+    /// Read the chain of ipv6 extension headers
+    ///
+    /// Parsing stops at the first unhandled next header
+    fn read_extensions(
+        next_header: IpProtocol,
+        mut rest: &BitSlice<Msb0, u8>,
+    ) -> Result<(&BitSlice<Msb0, u8>, Vec<Ipv6Extension>), DekuError> {
+        let mut extensions = Vec::new();
+        let mut next_header = next_header;
+
+        while let IpProtocol::IPV6ROUTE = next_header {
+            if extensions.len() == MAX_IPV6_EXTENSIONS {
+                return Err(DekuError::Parse(format!(
+                    "ipv6 extension headers exceed the maximum of {}",
+                    MAX_IPV6_EXTENSIONS
+                )));
+            }
+
+            let (new_rest, routing) = Ipv6Routing::read(rest, deku::ctx::Endian::Big)?;
+            rest = new_rest;
+
+            let extension = Ipv6Extension::Routing(routing);
+            next_header = extension.next_header();
+            extensions.push(extension);
+        }
+
+        Ok((rest, extensions))
+    }
+
+    /// Protocol of the payload following the extension headers
+    pub fn upper_protocol(&self) -> IpProtocol {
+        self.extensions
+            .last()
+            .map_or(self.next_header, |extension| extension.next_header())
+    }
+
+    /// Final destination address of the packet
+    ///
+    /// When a routing header has segments left, the final destination is the last segment of
+    /// the routing header instead of the destination address, see
+    /// [RFC8200 section 8.1](https://datatracker.ietf.org/doc/html/rfc8200#section-8.1).
+    pub fn final_destination(&self) -> u128 {
+        self.extensions
+            .iter()
+            .rev()
+            .find_map(|extension| match extension {
+                Ipv6Extension::Routing(routing) if routing.segments_left > 0 => {
+                    match &routing.data {
+                        Ipv6RoutingData::Type0 { addresses, .. } => addresses.last().copied(),
+                        Ipv6RoutingData::Srh { segments, .. } => segments.first().copied(),
+                        Ipv6RoutingData::Unknown { .. } => None,
+                    }
+                }
+                _ => None,
+            })
+            .unwrap_or(self.dst)
+    }
+
+    /// Length in bytes of the extension headers, part of the payload
+    pub fn extensions_length(&self) -> usize {
+        self.extensions.iter().map(Ipv6Extension::length).sum()
+    }
 }
 
 impl Default for Ipv6 {
@@ -78,6 +283,7 @@ impl Default for Ipv6 {
             hop_limit: 0,
             src: 0xff000000000000000000000000000000,
             dst: 0xff000000000000000000000000000000,
+            extensions: Vec::new(),
         }
     }
 }
@@ -85,11 +291,17 @@ impl Default for Ipv6 {
 impl Layer for Ipv6 {}
 impl LayerExt for Ipv6 {
     fn finalize(&mut self, _prev: &[LayerOwned], next: &[LayerOwned]) -> Result<(), LayerError> {
-        // Update length field
-        self.length =
-            u16::try_from(crate::layer::utils::length_of_layers(next)?).map_err(|_e| {
-                LayerError::Finalize("Could not convert layer length to u16".to_string())
-            })?;
+        // Update length field, the payload includes the extension headers
+        self.length = u16::try_from(
+            crate::layer::utils::length_of_layers(next)?
+                .checked_add(self.extensions_length())
+                .ok_or_else(|| {
+                    LayerError::Finalize(
+                        "Overflow occured when calculating ipv6 length".to_string(),
+                    )
+                })?,
+        )
+        .map_err(|_e| LayerError::Finalize("Could not convert layer length to u16".to_string()))?;
 
         // TODO: Update next header?
         Ok(())
@@ -107,6 +319,27 @@ impl LayerExt for Ipv6 {
     fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
         Ok(DekuContainerWrite::to_bytes(self)?)
     }
+
+    fn length_field(&self) -> Option<LengthField> {
+        Some(LengthField {
+            name: "payload length",
+            value: self.length,
+            includes_header: false,
+            extensions_length: self.extensions_length(),
+        })
+    }
+
+    /// Validate the version
+    fn validate(&self) -> Result<(), LayerError> {
+        if self.version != 6 {
+            return Err(LayerError::Validate(format!(
+                "invalid ipv6 version {}",
+                self.version
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -171,12 +404,216 @@ mod tests {
                 hop_limit: 64,
                 src: 0x3ffe802000000001026097fffe0769ea,
                 dst: 0x3ffe050100001c010200f8fffe03d9c0,
+                extensions: vec![],
+            }
+        ),
+        case::srh(
+            &hex!("6000000000302b4020010db80000000000000000000000aa20010db8000000000000000000000001110404010100000020010db800000000000000000000000220010db8000000000000000000000001"),
+            Ipv6 {
+                version: 6,
+                ds: 0,
+                ecn: 0,
+                label: 0,
+                length: 48,
+                next_header: IpProtocol::IPV6ROUTE,
+                hop_limit: 64,
+                src: 0x20010db80000000000000000000000aa,
+                dst: 0x20010db8000000000000000000000001,
+                extensions: vec![Ipv6Extension::Routing(Ipv6Routing {
+                    next_header: IpProtocol::UDP,
+                    length: 4,
+                    routing_type: 4,
+                    segments_left: 1,
+                    data: Ipv6RoutingData::Srh {
+                        last_entry: 1,
+                        flags: 0,
+                        tag: 0,
+                        segments: vec![
+                            0x20010db8000000000000000000000002,
+                            0x20010db8000000000000000000000001,
+                        ],
+                        tlvs: vec![],
+                    },
+                })],
+            }
+        ),
+        case::type0(
+            &hex!("6000000000182b4020010db80000000000000000000000aa20010db8000000000000000000000001110200010000000020010db8000000000000000000000002"),
+            Ipv6 {
+                version: 6,
+                ds: 0,
+                ecn: 0,
+                label: 0,
+                length: 24,
+                next_header: IpProtocol::IPV6ROUTE,
+                hop_limit: 64,
+                src: 0x20010db80000000000000000000000aa,
+                dst: 0x20010db8000000000000000000000001,
+                extensions: vec![Ipv6Extension::Routing(Ipv6Routing {
+                    next_header: IpProtocol::UDP,
+                    length: 2,
+                    routing_type: 0,
+                    segments_left: 1,
+                    data: Ipv6RoutingData::Type0 {
+                        reserved: 0,
+                        addresses: vec![0x20010db8000000000000000000000002],
+                    },
+                })],
+            }
+        ),
+        case::unknown_routing_type(
+            &hex!("6000000000082b4020010db80000000000000000000000aa20010db80000000000000000000000011100030100000000"),
+            Ipv6 {
+                version: 6,
+                ds: 0,
+                ecn: 0,
+                label: 0,
+                length: 8,
+                next_header: IpProtocol::IPV6ROUTE,
+                hop_limit: 64,
+                src: 0x20010db80000000000000000000000aa,
+                dst: 0x20010db8000000000000000000000001,
+                extensions: vec![Ipv6Extension::Routing(Ipv6Routing {
+                    next_header: IpProtocol::UDP,
+                    length: 0,
+                    routing_type: 3,
+                    segments_left: 1,
+                    data: Ipv6RoutingData::Unknown {
+                        value: vec![0, 0, 0, 0],
+                    },
+                })],
             }
         ),
     )]
     fn test_ipv6_rw(input: &[u8], expected: Ipv6) {
         let ipv6 = Ipv6::try_from(input).unwrap();
         assert_eq!(expected, ipv6);
+
+        let ret_write = LayerExt::to_bytes(&ipv6).unwrap();
+        assert_eq!(input.to_vec(), ret_write);
+    }
+
+    #[test]
+    fn test_ipv6_upper_protocol() {
+        let mut ipv6 = Ipv6 {
+            next_header: IpProtocol::TCP,
+            ..Default::default()
+        };
+        assert_eq!(IpProtocol::TCP, ipv6.upper_protocol());
+
+        ipv6.next_header = IpProtocol::IPV6ROUTE;
+        ipv6.extensions.push(Ipv6Extension::Routing(Ipv6Routing {
+            next_header: IpProtocol::UDP,
+            length: 0,
+            routing_type: 3,
+            segments_left: 0,
+            data: Ipv6RoutingData::Unknown {
+                value: vec![0, 0, 0, 0],
+            },
+        }));
+        assert_eq!(IpProtocol::UDP, ipv6.upper_protocol());
+    }
+
+    #[test]
+    fn test_ipv6_validate() {
+        let ipv6 = Ipv6 {
+            version: 6,
+            ..Default::default()
+        };
+        assert_eq!(Ok(()), ipv6.validate());
+
+        let ipv6 = Ipv6 {
+            version: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            Err(LayerError::Validate("invalid ipv6 version 4".to_string())),
+            ipv6.validate()
+        );
+    }
+
+    #[rstest(count, expected,
+        case::max(MAX_IPV6_EXTENSIONS, Ok(MAX_IPV6_EXTENSIONS)),
+        case::exceeded(
+            MAX_IPV6_EXTENSIONS + 1,
+            Err(LayerError::Parse("Parse error: ipv6 extension headers exceed the maximum of 8".to_string()))
+        ),
+        case::many(1000, Err(LayerError::Parse("Parse error: ipv6 extension headers exceed the maximum of 8".to_string()))),
+    )]
+    fn test_ipv6_extensions_limit(count: usize, expected: Result<usize, LayerError>) {
+        // chain of empty type 0 routing headers, the last one followed by udp
+        let mut input = hex!(
+            "6000000000002b40 20010db80000000000000000000000aa 20010db8000000000000000000000001"
+        )
+        .to_vec();
+        for i in 0..count {
+            let next_header = if i + 1 == count { 0x11 } else { 0x2b };
+            input.extend_from_slice(&[next_header, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        }
+
+        assert_eq!(
+            expected,
+            Ipv6::parse(&input).map(|(_rest, ipv6)| ipv6.extensions.len())
+        );
+    }
+
+    #[test]
+    fn test_ipv6_type0_odd_length() {
+        let input = hex!("6000000000102b4020010db80000000000000000000000aa20010db8000000000000000000000001 1101000100000000 0000000000000000");
+        assert_eq!(
+            Err(LayerError::Parse(
+                "Parse error: odd length of ipv6 type 0 routing header".to_string()
+            )),
+            Ipv6::parse(&input).map(|(_rest, ipv6)| ipv6.extensions.len())
+        );
+    }
+
+    #[rstest(
+        routing_type,
+        segments_left,
+        expected,
+        case::srh(4, 1, 0x20010db8000000000000000000000003),
+        case::srh_arrived(4, 0, 0x20010db8000000000000000000000001),
+        case::type0(0, 1, 0x20010db8000000000000000000000002),
+        case::type0_arrived(0, 0, 0x20010db8000000000000000000000001),
+        case::unknown(3, 1, 0x20010db8000000000000000000000001)
+    )]
+    fn test_ipv6_final_destination(routing_type: u8, segments_left: u8, expected: u128) {
+        let addresses = vec![
+            0x20010db8000000000000000000000003,
+            0x20010db8000000000000000000000002,
+        ];
+        let data = match routing_type {
+            0 => Ipv6RoutingData::Type0 {
+                reserved: 0,
+                addresses,
+            },
+            4 => Ipv6RoutingData::Srh {
+                last_entry: 1,
+                flags: 0,
+                tag: 0,
+                segments: addresses,
+                tlvs: vec![],
+            },
+            _ => Ipv6RoutingData::Unknown {
+                value: vec![0, 0, 0, 0],
+            },
+        };
+
+        let mut ipv6 = Ipv6 {
+            dst: 0x20010db8000000000000000000000001,
+            ..Default::default()
+        };
+        assert_eq!(0x20010db8000000000000000000000001, ipv6.final_destination());
+
+        ipv6.extensions.push(Ipv6Extension::Routing(Ipv6Routing {
+            next_header: IpProtocol::UDP,
+            length: 4,
+            routing_type,
+            segments_left,
+            data,
+        }));
+        assert_eq!(expected, ipv6.final_destination());
     }
 
     #[test]
@@ -192,6 +629,7 @@ mod tests {
                 hop_limit: 0,
                 src: 0xff000000000000000000000000000000,
                 dst: 0xff000000000000000000000000000000,
+                extensions: vec![],
             },
             Ipv6::default(),
         );
@@ -228,4 +666,27 @@ mod tests {
         };
         assert_eq!(expected_ipv6, ipv6);
     }
+
+    #[test]
+    fn test_ipv6_finalize_length_extensions() {
+        let mut ipv6 = Ipv6 {
+            next_header: IpProtocol::IPV6ROUTE,
+            extensions: vec![Ipv6Extension::Routing(Ipv6Routing {
+                next_header: IpProtocol::UDP,
+                length: 2,
+                routing_type: 0,
+                segments_left: 1,
+                data: Ipv6RoutingData::Type0 {
+                    reserved: 0,
+                    addresses: vec![0x20010db8000000000000000000000002],
+                },
+            })],
+            ..Default::default()
+        };
+
+        // Payload length should include the extension headers
+        ipv6.finalize(&[], &[Layer100::boxed()]).unwrap();
+
+        assert_eq!(124, ipv6.length);
+    }
 }
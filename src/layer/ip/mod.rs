@@ -10,24 +10,125 @@ pub use ipv4::Ipv4;
 pub use ipv6::Ipv6;
 pub use protocols::IpProtocol;
 
+use alloc::vec::Vec;
 use core::convert::TryInto;
+use deku::prelude::*;
 
 /// 16-bit ip checksum
 pub fn checksum(input: &[u8]) -> u16 {
-    let mut sum = 0x00;
-    let mut chunks_iter = input.chunks_exact(2);
-    for chunk in &mut chunks_iter {
-        sum += u32::from(u16::from_be_bytes(
-            chunk.try_into().expect("chunks of 2 bytes"),
-        ));
+    let mut checksum = Checksum::new();
+    checksum.add_bytes(input);
+    checksum.finish()
+}
+
+/**
+Streaming 16-bit ip checksum
+
+Data can be added in chunks of any length, the result is the same as [checksum] over the
+concatenated data. This avoids building a single buffer of the data to checksum.
+
+```rust
+# use hatchet::layer::ip::{checksum, Checksum};
+let mut chksum = Checksum::new();
+chksum.add_bytes(&[0x45, 0x00, 0x00]);
+chksum.add_bytes(&[0x73, 0x00]);
+
+assert_eq!(checksum(&[0x45, 0x00, 0x00, 0x73, 0x00]), chksum.finish());
+```
+*/
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Checksum {
+    sum: u64,
+    /// Trailing byte of an odd length chunk, waiting for the next byte
+    pending: Option<u8>,
+}
+
+impl Checksum {
+    /// Create an empty checksum accumulator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add data following the previously added data
+    pub fn add_bytes(&mut self, input: &[u8]) {
+        let mut input = input;
+
+        if let Some(high) = self.pending.take() {
+            match input.split_first() {
+                Some((low, rest)) => {
+                    self.sum += u64::from(u16::from_be_bytes([high, *low]));
+                    input = rest;
+                }
+                None => {
+                    self.pending = Some(high);
+                    return;
+                }
+            }
+        }
+
+        let mut chunks_iter = input.chunks_exact(2);
+        for chunk in &mut chunks_iter {
+            self.sum += u64::from(u16::from_be_bytes(
+                chunk.try_into().expect("chunks of 2 bytes"),
+            ));
+        }
+
+        if let [rem] = chunks_iter.remainder() {
+            self.pending = Some(*rem);
+        }
+    }
+
+    /// Add the data of another accumulator
+    ///
+    /// The data of each accumulator is padded to an even length, as if `other` was
+    /// started on a 16-bit boundary.
+    pub fn merge(&mut self, other: &Checksum) {
+        self.sum = self.padded_sum() + other.padded_sum();
+        self.pending = None;
     }
 
-    if let [rem] = chunks_iter.remainder() {
-        sum += u32::from(u16::from_be_bytes([*rem, 0x00]));
+    /// Compute the checksum of the added data
+    pub fn finish(&self) -> u16 {
+        let mut sum = self.padded_sum();
+        while sum >> 16 != 0 {
+            sum = (sum & 0xffff) + (sum >> 16);
+        }
+
+        !(sum as u16)
+    }
+
+    fn padded_sum(&self) -> u64 {
+        self.sum
+            + self
+                .pending
+                .map_or(0, |rem| u64::from(u16::from_be_bytes([rem, 0x00])))
     }
+}
 
-    let carry_add = (sum & 0xffff) + (sum >> 16);
-    !(((carry_add & 0xffff) + (carry_add >> 16)) as u16)
+/// Ipv6 pseudo-header covered by upper-layer checksums
+///
+/// The destination is the [final destination](Ipv6::final_destination) of the packet, see
+/// [RFC8200 section 8.1](https://datatracker.ietf.org/doc/html/rfc8200#section-8.1).
+#[derive(Debug, PartialEq, Clone, DekuWrite)]
+#[deku(endian = "big")]
+pub(crate) struct Ipv6PseudoHeader {
+    src: u128,
+    dst: u128,
+    length: u32,
+    zeros: [u8; 3],
+    next_header: IpProtocol,
+}
+
+impl Ipv6PseudoHeader {
+    pub(crate) fn new(ipv6: &Ipv6, next_header: IpProtocol, length: u32) -> Self {
+        Ipv6PseudoHeader {
+            src: ipv6.src,
+            dst: ipv6.final_destination(),
+            length,
+            zeros: [0; 3],
+            next_header,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -47,4 +148,30 @@ mod tests {
         let chksum = checksum(&input);
         assert_eq!(expected, chksum);
     }
+
+    #[rstest(split, case(0), case(1), case(2), case(3), case(20), case(21))]
+    fn test_checksum_chunks(split: usize) {
+        let input = hex!("45000073000040004011 0000 c0a80001c0a800c7aa");
+        let (first, second) = input.split_at(split);
+
+        let mut chksum = Checksum::new();
+        chksum.add_bytes(first);
+        chksum.add_bytes(&[]);
+        chksum.add_bytes(second);
+
+        assert_eq!(checksum(&input), chksum.finish());
+    }
+
+    #[test]
+    fn test_checksum_merge() {
+        let mut first = Checksum::new();
+        first.add_bytes(&hex!("4500007300004000"));
+
+        let mut second = Checksum::new();
+        second.add_bytes(&hex!("4011 0000 c0a80001c0a800c7"));
+        second.add_bytes(&hex!("aa"));
+
+        first.merge(&second);
+        assert_eq!(0x0E61, first.finish());
+    }
 }
@@ -0,0 +1,167 @@
+/*!
+LLC and SNAP layers
+
+IEEE 802.2 Logical Link Control, carried by IEEE 802.3 frames, and the Subnetwork Access
+Protocol extension identifying the payload by an organization and protocol id
+*/
+
+use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
+use alloc::{format, vec::Vec};
+use deku::prelude::*;
+
+/// Service access point announcing a [Snap] header
+pub const LLC_SAP_SNAP: u8 = 0xAA;
+
+/// Unnumbered Information control field, used with [Snap]
+pub const LLC_CONTROL_UI: u8 = 0x03;
+
+/// Length of the SNAP header
+pub const SNAP_HEADER_LENGTH: usize = 5;
+
+/**
+LLC Header
+
+```text
+ 0                   1                   2
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|      DSAP     |      SSAP     |    Control    | Control (opt) |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+```
+
+The control field is 2 bytes for information and supervisory frames, 1 byte for unnumbered
+frames.
+*/
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(endian = "big")]
+pub struct Llc {
+    /// Destination Service Access Point
+    pub dsap: u8,
+    /// Source Service Access Point
+    pub ssap: u8,
+    /// Control
+    pub control: u8,
+    /// Second control byte, present unless the frame is unnumbered
+    #[deku(cond = "*control & 0x03 != 0x03")]
+    pub control_ext: Option<u8>,
+}
+
+impl Llc {
+    /// Returns true if the header is followed by a [Snap] header
+    pub fn is_snap(&self) -> bool {
+        self.dsap == LLC_SAP_SNAP && self.ssap == LLC_SAP_SNAP && self.control == LLC_CONTROL_UI
+    }
+}
+
+impl Default for Llc {
+    fn default() -> Self {
+        Llc {
+            dsap: LLC_SAP_SNAP,
+            ssap: LLC_SAP_SNAP,
+            control: LLC_CONTROL_UI,
+            control_ext: None,
+        }
+    }
+}
+
+impl Layer for Llc {}
+impl LayerExt for Llc {
+    fn finalize(&mut self, _prev: &[LayerOwned], _next: &[LayerOwned]) -> Result<(), LayerError> {
+        Ok(())
+    }
+
+    fn parse(input: &[u8]) -> Result<(&[u8], Self), LayerError>
+    where
+        Self: Sized,
+    {
+        let ((rest, bit_offset), llc) = Llc::from_bytes((input, 0))?;
+        debug_assert_eq!(0, bit_offset);
+        Ok((rest, llc))
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
+        Ok(DekuContainerWrite::to_bytes(self)?)
+    }
+}
+
+/**
+SNAP Header
+
+```text
+ 0                   1                   2                   3
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|      Organizationally Unique Identifier       |  Protocol ID  |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|  Protocol ID  |
++-+-+-+-+-+-+-+-+
+```
+*/
+#[derive(Debug, PartialEq, Clone, Default, DekuRead, DekuWrite)]
+#[deku(endian = "big")]
+pub struct Snap {
+    /// Organizationally Unique Identifier
+    #[deku(bits = "24")]
+    pub oui: u32,
+    /// Protocol ID, an ether type when the OUI is 0
+    pub protocol_id: u16,
+}
+
+impl Layer for Snap {}
+impl LayerExt for Snap {
+    fn finalize(&mut self, _prev: &[LayerOwned], _next: &[LayerOwned]) -> Result<(), LayerError> {
+        Ok(())
+    }
+
+    fn parse(input: &[u8]) -> Result<(&[u8], Self), LayerError>
+    where
+        Self: Sized,
+    {
+        let ((rest, bit_offset), snap) = Snap::from_bytes((input, 0))?;
+        debug_assert_eq!(0, bit_offset);
+        Ok((rest, snap))
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
+        Ok(DekuContainerWrite::to_bytes(self)?)
+    }
+
+    fn length(&self) -> Result<usize, LayerError> {
+        Ok(SNAP_HEADER_LENGTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hexlit::hex;
+    use rstest::*;
+    use std::convert::TryFrom;
+
+    #[rstest(input, expected, expected_snap,
+        case(&hex!("aaaa03"), Llc::default(), true),
+        case::stp(&hex!("424203"), Llc { dsap: 0x42, ssap: 0x42, control: 0x03, control_ext: None }, false),
+        case::information(&hex!("f0f00001"), Llc { dsap: 0xf0, ssap: 0xf0, control: 0x00, control_ext: Some(0x01) }, false),
+    )]
+    fn test_llc_rw(input: &[u8], expected: Llc, expected_snap: bool) {
+        let ret_read = Llc::try_from(input).unwrap();
+        assert_eq!(expected, ret_read);
+        assert_eq!(expected_snap, ret_read.is_snap());
+
+        let ret_write = LayerExt::to_bytes(&ret_read).unwrap();
+        assert_eq!(input.to_vec(), ret_write);
+    }
+
+    #[rstest(input, expected,
+        case(&hex!("00000c2000"), Snap { oui: 0x00000c, protocol_id: 0x2000 }),
+        case(&hex!("0000000800"), Snap { oui: 0, protocol_id: 0x0800 }),
+    )]
+    fn test_snap_rw(input: &[u8], expected: Snap) {
+        let ret_read = Snap::try_from(input).unwrap();
+        assert_eq!(expected, ret_read);
+
+        let ret_write = LayerExt::to_bytes(&ret_read).unwrap();
+        assert_eq!(input.to_vec(), ret_write);
+        assert_eq!(ret_write.len(), ret_read.length().unwrap());
+    }
+}
@@ -15,12 +15,17 @@ pub mod error;
 pub mod utils;
 pub use error::LayerError;
 
+pub mod cdp;
+pub mod dns;
 pub mod ether;
+pub mod gre;
 pub mod icmp;
 pub mod ip;
+pub mod llc;
 pub mod raw;
 pub mod tcp;
 pub mod udp;
+pub mod vxlan;
 
 #[doc(hidden)]
 pub trait AsAny {
@@ -80,6 +85,37 @@ pub trait LayerExt: core::fmt::Debug + Layer + LayerClone {
     fn length(&self) -> Result<usize, LayerError> {
         Ok(self.to_bytes()?.len())
     }
+
+    /// Return's the length field declared by the layer, if any
+    ///
+    /// Implement this method for layers carrying a length field (such as the ipv4 total
+    /// length) to allow comparing it against the serialized data.
+    fn length_field(&self) -> Option<LengthField> {
+        None
+    }
+
+    /// Validate the invariants of the layer
+    ///
+    /// Implement this method to catch malformed layers, such as a crafted layer with an
+    /// invalid header length, before sending them.
+    fn validate(&self) -> Result<(), LayerError> {
+        Ok(())
+    }
+}
+
+/// A length field declared by a layer, see [LayerExt::length_field]
+#[derive(Debug, PartialEq, Clone)]
+pub struct LengthField {
+    /// Name of the field
+    pub name: &'static str,
+    /// Declared length in bytes
+    pub value: u16,
+    /// Whether the declared length includes the layer itself or only the layers following it
+    pub includes_header: bool,
+    /// Length in bytes of extension headers held by the layer, covered by the declared length
+    /// when it does not include the layer itself, such as [Ipv6](crate::layer::ip::Ipv6)
+    /// extension headers
+    pub extensions_length: usize,
 }
 
 /// A reference to a [Layer](self::Layer)
@@ -2,11 +2,11 @@
 TCP layer
 */
 use crate::get_layer;
-use crate::layer::ip::{IpProtocol, Ipv4, Ipv6};
+use crate::layer::ip::{Checksum, IpProtocol, Ipv4, Ipv6, Ipv6PseudoHeader};
 use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
-use alloc::{format, string::ToString, vec::Vec};
+use alloc::{boxed::Box, format, string::ToString, vec::Vec};
 use core::convert::TryFrom;
-use deku::bitvec::{BitSlice, Msb0};
+use deku::bitvec::{BitSlice, BitView, Msb0};
 use deku::prelude::*;
 
 mod options;
@@ -101,7 +101,7 @@ TCP Header
 ```
 */
 #[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
-#[deku(endian = "big")]
+#[deku(endian = "big", ctx = "lenient: bool", ctx_default = "false")]
 #[allow(missing_docs)]
 pub struct Tcp {
     pub sport: u16,
@@ -115,14 +115,66 @@ pub struct Tcp {
     pub window: u16,
     pub checksum: u16,
     pub urgptr: u16,
-    #[deku(reader = "Tcp::read_options(*offset, deku::rest)")]
+    #[deku(reader = "Tcp::read_options(*offset, lenient, deku::rest)")]
     pub options: Vec<TcpOption>,
+    /// Option bytes which could not be parsed, see [Tcp::parse_lenient]
+    #[deku(reader = "Tcp::read_options_raw(*offset, lenient, deku::byte_offset, deku::rest)")]
+    pub options_raw: Vec<u8>,
 }
 
 impl Tcp {
+    /// Parse a tcp layer, storing unparseable options as raw bytes
+    ///
+    /// On an option which can't be parsed, the remaining option bytes are stored in
+    /// `options_raw` instead of returning an error, such that `to_bytes` reproduces the input.
+    pub fn parse_lenient(input: &[u8]) -> Result<(&[u8], Self), LayerError> {
+        let (rest, tcp) = Tcp::read(input.view_bits::<Msb0>(), true)?;
+        debug_assert_eq!(0, rest.len() % 8);
+        Ok((&input[input.len() - rest.len() / 8..], tcp))
+    }
+
+    /// Parse a tcp layer leniently, see [Tcp::parse_lenient]
+    ///
+    /// Returns a dyn Layer, to be used in [PacketParser](crate::packet::PacketParser) bindings
+    pub fn parse_layer_lenient(input: &[u8]) -> Result<(&[u8], Box<dyn LayerExt>), LayerError> {
+        Tcp::parse_lenient(input).map(|(rest, tcp)| (rest, Box::new(tcp) as Box<dyn LayerExt>))
+    }
+
+    /// Maximum Segment Size, from the [MSS](TcpOption::MSS) option
+    pub fn mss(&self) -> Option<u16> {
+        self.options.iter().find_map(|option| match option {
+            TcpOption::MSS { value, .. } => Some(*value),
+            _ => None,
+        })
+    }
+
+    /// Window scale shift count, from the [WScale](TcpOption::WScale) option
+    pub fn window_scale(&self) -> Option<u8> {
+        self.options.iter().find_map(|option| match option {
+            TcpOption::WScale { value, .. } => Some(*value),
+            _ => None,
+        })
+    }
+
+    /// Returns true if the [SAckOK](TcpOption::SAckOK) option is present
+    pub fn sack_permitted(&self) -> bool {
+        self.options
+            .iter()
+            .any(|option| matches!(option, TcpOption::SAckOK { .. }))
+    }
+
+    /// Timestamps, from the [Timestamp](TcpOption::Timestamp) option
+    pub fn timestamps(&self) -> Option<&TimestampData> {
+        self.options.iter().find_map(|option| match option {
+            TcpOption::Timestamp { value, .. } => Some(value),
+            _ => None,
+        })
+    }
+
     fn read_options(
         offset: u8,
-        rest: &BitSlice<Msb0, u8>,
+        lenient: bool,
+        input: &BitSlice<Msb0, u8>,
     ) -> Result<(&BitSlice<Msb0, u8>, Vec<TcpOption>), DekuError> {
         let length = offset
             .checked_sub(5)
@@ -130,25 +182,32 @@ impl Tcp {
             .ok_or_else(|| DekuError::Parse("error: invalid tcp offset".to_string()))?;
 
         if length == 0 {
-            return Ok((rest, Vec::new()));
+            return Ok((input, Vec::new()));
         }
 
         // slice off length from rest
         let bits: usize = length as usize * 8;
 
         // Check split_at precondition
-        if bits > rest.len() {
+        if bits > input.len() {
             return Err(DekuError::Parse(
                 "not enough data to read tcp options".to_string(),
             ));
         }
 
-        let (mut option_rest, rest) = rest.split_at(bits);
+        let (mut option_rest, rest) = input.split_at(bits);
 
         let mut tcp_options = Vec::with_capacity(1); // at-least 1
         while !option_rest.is_empty() {
             let (option_rest_new, tcp_option) =
-                TcpOption::read(option_rest, deku::ctx::Endian::Big)?;
+                match TcpOption::read(option_rest, deku::ctx::Endian::Big) {
+                    Ok(v) => v,
+                    // leave the unparsed option bytes to `read_options_raw`
+                    Err(_e) if lenient => {
+                        return Ok((&input[bits - option_rest.len()..], tcp_options))
+                    }
+                    Err(e) => return Err(e),
+                };
 
             tcp_options.push(tcp_option);
 
@@ -157,6 +216,27 @@ impl Tcp {
 
         Ok((rest, tcp_options))
     }
+
+    /// Read the option bytes left unparsed by `read_options`
+    ///
+    /// `byte_offset` is the number of header bytes read so far, the remaining option bytes
+    /// extend up to the data offset. Only lenient parsing leaves option bytes unparsed.
+    fn read_options_raw(
+        offset: u8,
+        lenient: bool,
+        byte_offset: usize,
+        rest: &BitSlice<Msb0, u8>,
+    ) -> Result<(&BitSlice<Msb0, u8>, Vec<u8>), DekuError> {
+        if !lenient {
+            return Ok((rest, Vec::new()));
+        }
+
+        let length = (offset as usize)
+            .saturating_mul(4)
+            .saturating_sub(byte_offset);
+
+        Vec::<u8>::read(rest, (deku::ctx::Limit::new_count(length), ()))
+    }
 }
 
 impl Default for Tcp {
@@ -172,29 +252,7 @@ impl Default for Tcp {
             checksum: 0,
             urgptr: 0,
             options: Vec::new(),
-        }
-    }
-}
-
-/// Ipv6 pseudo header used in tcp checksum calculation
-#[derive(Debug, PartialEq, Clone, DekuWrite)]
-#[deku(endian = "big")]
-struct Ipv6PseudoHeader {
-    src: u128,
-    dst: u128,
-    length: u32,
-    zeros: [u8; 3],
-    next_header: IpProtocol,
-}
-
-impl Ipv6PseudoHeader {
-    fn new(ipv6: &Ipv6, tcp_length: u32) -> Self {
-        Ipv6PseudoHeader {
-            src: ipv6.src,
-            dst: ipv6.dst,
-            length: tcp_length,
-            zeros: [0; 3],
-            next_header: ipv6.next_header,
+            options_raw: Vec::new(),
         }
     }
 }
@@ -222,8 +280,38 @@ impl Ipv4PseudoHeader {
     }
 }
 
+impl Tcp {
+    /// Finalize the layer, erroring if the checksum cannot be computed
+    ///
+    /// Unlike [finalize](LayerExt::finalize), which leaves the checksum untouched when the
+    /// previous layer is not [Ipv4] or [Ipv6], this returns a [LayerError::Finalize] since
+    /// the pseudo-header required for the checksum is unavailable.
+    pub fn finalize_strict(
+        &mut self,
+        prev: &[LayerOwned],
+        next: &[LayerOwned],
+    ) -> Result<(), LayerError> {
+        let has_ip = matches!(
+            prev.last(),
+            Some(l) if get_layer!(l, Ipv4).is_some() || get_layer!(l, Ipv6).is_some()
+        );
+
+        if !has_ip {
+            return Err(LayerError::Finalize(
+                "Tcp checksum requires a preceding Ipv4 or Ipv6 layer".to_string(),
+            ));
+        }
+
+        LayerExt::finalize(self, prev, next)
+    }
+}
+
 impl Layer for Tcp {}
 impl LayerExt for Tcp {
+    /// Update the offset and checksum fields, padding options to a 32-bit boundary
+    ///
+    /// The checksum is computed over the Ipv4 or Ipv6 pseudo-header, it is left as-is if the
+    /// previous layer is not an ip layer. See [Tcp::finalize_strict] to error instead.
     fn finalize(&mut self, prev: &[LayerOwned], next: &[LayerOwned]) -> Result<(), LayerError> {
         let tcp_header = {
             let data = LayerExt::to_bytes(self)?; // TODO: We could verify options length instead
@@ -233,8 +321,19 @@ impl LayerExt for Tcp {
             for _ in 0..pad_amt {
                 self.options.push(TcpOption::EOL);
             }
+            let aligned_len = data.len() + pad_amt;
+
+            // The offset is covered by the checksum, update it before serializing the header
+            self.offset = u8::try_from(aligned_len / 4).map_err(|_e| {
+                LayerError::Finalize("Failed to convert tcp offset to u8".to_string())
+            })?;
 
             let mut data = LayerExt::to_bytes(self)?;
+            debug_assert_eq!(
+                aligned_len,
+                data.len(),
+                "dev error: tcp header should be aligned"
+            );
 
             // Clear checksum bytes for calculation
             data[16] = 0x00;
@@ -246,17 +345,21 @@ impl LayerExt for Tcp {
 
         // Update the tcp checksum
         if let Some(prev_layer) = prev.last() {
-            let tcp_payload = crate::layer::utils::layers_to_bytes(next)?;
+            let mut tcp_checksum = Checksum::new();
+            tcp_checksum.add_bytes(&tcp_header);
 
             // length of tcp header + tcp_payload
-            let tcp_length = tcp_header_len
-                .checked_add(tcp_payload.len())
-                .ok_or_else(|| {
+            let mut tcp_length = tcp_header_len;
+            for layer in next {
+                let data = layer.to_bytes()?;
+                tcp_length = tcp_length.checked_add(data.len()).ok_or_else(|| {
                     LayerError::Finalize(
                         "Overflow occured when calculating length for tcp (v4) checksum"
                             .to_string(),
                     )
                 })?;
+                tcp_checksum.add_bytes(&data);
+            }
 
             let ip_pseudo_header = if let Some(ipv4) = get_layer!(prev_layer, Ipv4) {
                 Some(
@@ -272,6 +375,7 @@ impl LayerExt for Tcp {
                 Some(
                     Ipv6PseudoHeader::new(
                         ipv6,
+                        ipv6.upper_protocol(),
                         u32::try_from(tcp_length).map_err(|_e| {
                             LayerError::Finalize("Failed to convert tcp_length to u32".to_string())
                         })?,
@@ -283,23 +387,14 @@ impl LayerExt for Tcp {
             };
 
             if let Some(ip_pseudo_header) = ip_pseudo_header {
-                let mut data = ip_pseudo_header;
-                data.extend(tcp_header);
-                data.extend(tcp_payload);
+                let mut checksum = Checksum::new();
+                checksum.add_bytes(&ip_pseudo_header);
+                checksum.merge(&tcp_checksum);
 
-                self.checksum = super::ip::checksum(&data)
+                self.checksum = checksum.finish()
             }
         }
 
-        debug_assert_eq!(
-            0,
-            tcp_header_len % 4,
-            "dev error: tcp header should be aligned"
-        );
-        // Update offset
-        self.offset = u8::try_from(tcp_header_len / 4)
-            .map_err(|_e| LayerError::Finalize("Failed to convert tcp offset to u8".to_string()))?;
-
         Ok(())
     }
 
@@ -315,12 +410,32 @@ impl LayerExt for Tcp {
     fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
         Ok(DekuContainerWrite::to_bytes(self)?)
     }
+
+    /// Validate the offset is consistent with the length of the header and options
+    fn validate(&self) -> Result<(), LayerError> {
+        if self.offset < 5 {
+            return Err(LayerError::Validate(format!(
+                "invalid tcp offset {}",
+                self.offset
+            )));
+        }
+
+        let header_len = self.length()?;
+        if usize::from(self.offset) * 4 != header_len {
+            return Err(LayerError::Validate(format!(
+                "tcp offset {} does not match header length {}",
+                self.offset, header_len
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::layer::{Layer, LayerError, LayerExt};
+    use crate::layer::{raw::Raw, Layer, LayerError, LayerExt};
     use alloc::boxed::Box;
     use hexlit::hex;
     use rstest::*;
@@ -380,6 +495,7 @@ mod tests {
                 checksum: 0xa958,
                 urgptr: 0,
                 options: Vec::new(),
+                options_raw: Vec::new(),
             },
         ),
         case(
@@ -408,7 +524,8 @@ mod tests {
                         length: 10,
                         value: vec![SAckData { begin: 3839279344, end: 3839282080 }]
                     },
-                ]
+                ],
+                options_raw: vec![],
             },
         ),
         #[should_panic(expected = "error: invalid tcp offset")]
@@ -444,6 +561,7 @@ mod tests {
                 checksum: 0,
                 urgptr: 0,
                 options: Vec::new(),
+                options_raw: Vec::new(),
             },
             Tcp::default()
         )
@@ -511,6 +629,37 @@ mod tests {
         assert_eq!(expected_checksum, tcp.checksum);
     }
 
+    #[rstest(
+        ip_layer,
+        case::v4(Box::new(Ipv4::default())),
+        case::v6(Box::new(Ipv6::default()))
+    )]
+    fn test_tcp_finalize_checksum_split_payload(ip_layer: LayerOwned) {
+        let raw = |data: &[u8]| -> LayerOwned {
+            Box::new(Raw {
+                data: data.to_vec(),
+                bit_offset: 0,
+            })
+        };
+
+        // Checksum over a payload split in odd sized layers
+        let mut tcp = Tcp::default();
+        tcp.finalize(
+            core::slice::from_ref(&ip_layer),
+            &[raw(b"abc"), raw(b"defgh"), raw(b"i")],
+        )
+        .unwrap();
+
+        // Checksum over the concatenated payload
+        let mut expected_tcp = Tcp::default();
+        expected_tcp
+            .finalize(&[ip_layer], &[raw(b"abcdefghi")])
+            .unwrap();
+
+        assert_ne!(0, tcp.checksum);
+        assert_eq!(expected_tcp.checksum, tcp.checksum);
+    }
+
     #[test]
     fn test_tcp_finalize_checksum_v6() {
         let expected_checksum = 0xB0E6;
@@ -528,6 +677,48 @@ mod tests {
         assert_eq!(expected_checksum, tcp.checksum);
     }
 
+    #[test]
+    fn test_tcp_finalize_checksum_v6_routing() {
+        use crate::layer::ip::ipv6::{Ipv6Extension, Ipv6Routing, Ipv6RoutingData};
+
+        let final_destination = 0x20010db8000000000000000000000002;
+
+        // Ipv6 / SRH, the destination address is the next segment
+        let routed: LayerOwned = Box::new(Ipv6 {
+            next_header: IpProtocol::IPV6ROUTE,
+            dst: 0x20010db8000000000000000000000001,
+            extensions: vec![Ipv6Extension::Routing(Ipv6Routing {
+                next_header: IpProtocol::TCP,
+                length: 4,
+                routing_type: 4,
+                segments_left: 1,
+                data: Ipv6RoutingData::Srh {
+                    last_entry: 1,
+                    flags: 0,
+                    tag: 0,
+                    segments: vec![final_destination, 0x20010db8000000000000000000000001],
+                    tlvs: vec![],
+                },
+            })],
+            ..Default::default()
+        });
+
+        // Ipv6 arrived at the final destination
+        let direct: LayerOwned = Box::new(Ipv6 {
+            next_header: IpProtocol::TCP,
+            dst: final_destination,
+            ..Default::default()
+        });
+
+        let mut tcp = Tcp::default();
+        tcp.finalize(&[routed], &[]).unwrap();
+
+        let mut expected_tcp = Tcp::default();
+        expected_tcp.finalize(&[direct], &[]).unwrap();
+
+        assert_eq!(expected_tcp.checksum, tcp.checksum);
+    }
+
     #[test]
     fn test_tcp_finalize() {
         let mut tcp = Tcp::default();
@@ -540,7 +731,7 @@ mod tests {
 
         // Only these fields should change during a finalize
         let expected_tcp = Tcp {
-            checksum: 0xB07A,
+            checksum: 0xA07A,
             offset: 6,
             options: vec![
                 TcpOption::NOP,
@@ -553,4 +744,127 @@ mod tests {
 
         assert_eq!(expected_tcp, tcp);
     }
+
+    #[test]
+    fn test_tcp_finalize_standalone() {
+        let mut tcp = Tcp {
+            checksum: 0xAABB,
+            ..Default::default()
+        };
+
+        // No preceding ip layer, the checksum is left as-is
+        tcp.finalize(&[], &[Layer100::boxed()]).unwrap();
+        assert_eq!(0xAABB, tcp.checksum);
+    }
+
+    #[rstest(offset, options, expected,
+        case::no_options(5, vec![], Ok(())),
+        case::options(6, vec![TcpOption::NOP, TcpOption::EOL, TcpOption::EOL, TcpOption::EOL], Ok(())),
+        case::invalid_offset(4, vec![], Err(LayerError::Validate("invalid tcp offset 4".to_string()))),
+        case::options_mismatch(5, vec![TcpOption::NOP, TcpOption::EOL, TcpOption::EOL, TcpOption::EOL],
+            Err(LayerError::Validate("tcp offset 5 does not match header length 24".to_string()))),
+    )]
+    fn test_tcp_validate(offset: u8, options: Vec<TcpOption>, expected: Result<(), LayerError>) {
+        let tcp = Tcp {
+            offset,
+            options,
+            ..Default::default()
+        };
+        assert_eq!(expected, tcp.validate());
+    }
+
+    #[rstest(input, expected_options, expected_raw,
+        case::valid(&hex!("04d200500000000100000000600204000000000001010101"), vec![TcpOption::NOP; 4], vec![]),
+        case::unknown_kind(&hex!("04d2005000000001000000006002040000000000011e0200"), vec![TcpOption::NOP], vec![0x1e, 0x02, 0x00]),
+        case::sack_length_underflow(&hex!("04d200500000000100000000600204000000000005000000"), vec![], vec![0x05, 0x00, 0x00, 0x00]),
+    )]
+    fn test_tcp_parse_lenient(
+        input: &[u8],
+        expected_options: Vec<TcpOption>,
+        expected_raw: Vec<u8>,
+    ) {
+        let (rest, tcp) = Tcp::parse_lenient(input).unwrap();
+        assert_eq!(expected_options, tcp.options);
+        assert_eq!(expected_raw, tcp.options_raw);
+        assert_eq!(&input[24..], rest);
+
+        // round-trip
+        assert_eq!(&input[..24], LayerExt::to_bytes(&tcp).unwrap());
+    }
+
+    #[test]
+    fn test_tcp_parse_strict_malformed_option() {
+        let input = hex!("04d2005000000001000000006002040000000000011e0200");
+        assert!(<Tcp as LayerExt>::parse(&input).is_err());
+    }
+
+    #[test]
+    fn test_tcp_option_helpers() {
+        // SYN with MSS, SAckOK, Timestamp, NOP, WScale options
+        let input = hex!(
+            "c3500050 01020304 00000000 a002faf0 00000000
+            020405b4 0402 080a0001e24000000000 01 030307"
+        );
+        let tcp = Tcp::try_from(&input[..]).unwrap();
+        assert_eq!(1, tcp.flags.syn);
+
+        assert_eq!(Some(1460), tcp.mss());
+        assert_eq!(Some(7), tcp.window_scale());
+        assert!(tcp.sack_permitted());
+        assert_eq!(
+            Some(&TimestampData {
+                start: 123456,
+                end: 0
+            }),
+            tcp.timestamps()
+        );
+
+        let tcp = Tcp::default();
+        assert_eq!(None, tcp.mss());
+        assert_eq!(None, tcp.window_scale());
+        assert!(!tcp.sack_permitted());
+        assert_eq!(None, tcp.timestamps());
+    }
+
+    #[test]
+    fn test_tcp_md5_option() {
+        // BGP SYN with MSS, NOP, NOP, MD5 signature options
+        let input = hex!(
+            "d6d300b3 6c1b7a95 00000000 b0024000 1e2f0000
+            020405b4 0101 1312 5c6f9a0e2b7d44c1a83b19f2e06d7748"
+        );
+        let tcp = Tcp::try_from(&input[..]).unwrap();
+        assert_eq!(179, tcp.dport);
+        assert_eq!(
+            vec![
+                TcpOption::MSS {
+                    length: 4,
+                    value: 1460
+                },
+                TcpOption::NOP,
+                TcpOption::NOP,
+                TcpOption::Md5 {
+                    length: 18,
+                    digest: hex!("5c6f9a0e2b7d44c1a83b19f2e06d7748"),
+                },
+            ],
+            tcp.options
+        );
+        assert!(tcp.options_raw.is_empty());
+
+        assert_eq!(input.to_vec(), LayerExt::to_bytes(&tcp).unwrap());
+    }
+
+    #[test]
+    fn test_tcp_finalize_strict() {
+        let mut tcp = Tcp::default();
+        assert!(matches!(
+            tcp.finalize_strict(&[], &[Layer100::boxed()]),
+            Err(LayerError::Finalize(_))
+        ));
+
+        let ipv4 = Box::new(Ipv4::default());
+        tcp.finalize_strict(&[ipv4], &[Layer100::boxed()]).unwrap();
+        assert_eq!(0xB17E, tcp.checksum);
+    }
 }
@@ -53,6 +53,9 @@ pub enum TcpOption {
     },
     #[deku(id = "0x08")]
     Timestamp { length: u8, value: TimestampData },
+    /// MD5 Signature, [RFC2385](https://datatracker.ietf.org/doc/html/rfc2385)
+    #[deku(id = "0x13")]
+    Md5 { length: u8, digest: [u8; 16] },
 }
 
 #[cfg(test)]
@@ -78,6 +81,10 @@ mod tests {
                 end: 1081292766
             }
         }),
+        case(&hex!("1312000102030405060708090a0b0c0d0e0f"), TcpOption::Md5 {
+            length: 18,
+            digest: hex!("000102030405060708090a0b0c0d0e0f"),
+        }),
 
 
         // Errors
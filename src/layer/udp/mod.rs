@@ -3,8 +3,8 @@ UDP layer
 */
 
 use crate::get_layer;
-use crate::layer::ip::{IpProtocol, Ipv4, Ipv6};
-use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
+use crate::layer::ip::{Checksum, IpProtocol, Ipv4, Ipv6, Ipv6PseudoHeader};
+use crate::layer::{Layer, LayerError, LayerExt, LayerOwned, LengthField};
 use alloc::{format, string::ToString, vec::Vec};
 use core::convert::TryFrom;
 use deku::prelude::*;
@@ -35,6 +35,9 @@ pub struct Udp {
     pub checksum: u16,
 }
 
+/// Length of the udp header in bytes
+const UDP_HEADER_LENGTH: usize = 8;
+
 impl Default for Udp {
     fn default() -> Self {
         Udp {
@@ -46,29 +49,6 @@ impl Default for Udp {
     }
 }
 
-/// Ipv6 pseudo header used in udp checksum calculation
-#[derive(Debug, PartialEq, Clone, DekuWrite)]
-#[deku(endian = "big")]
-struct Ipv6PseudoHeader {
-    src: u128,
-    dst: u128,
-    length: u32,
-    zeros: [u8; 3],
-    next_header: IpProtocol,
-}
-
-impl Ipv6PseudoHeader {
-    fn new(ipv6: &Ipv6, udp_length: u32) -> Self {
-        Ipv6PseudoHeader {
-            src: ipv6.src,
-            dst: ipv6.dst,
-            length: udp_length,
-            zeros: [0; 3],
-            next_header: ipv6.next_header,
-        }
-    }
-}
-
 /// Ipv4 pseudo header used in udp checksum calculation
 #[derive(Debug, PartialEq, Clone, DekuWrite)]
 #[deku(endian = "big")]
@@ -92,35 +72,94 @@ impl Ipv4PseudoHeader {
     }
 }
 
-impl Layer for Udp {}
-impl LayerExt for Udp {
-    fn finalize(&mut self, prev: &[LayerOwned], next: &[LayerOwned]) -> Result<(), LayerError> {
-        let udp_header = {
-            let mut data = LayerExt::to_bytes(self)?;
+impl Udp {
+    /// Finalize the layer, erroring if the checksum cannot be computed
+    ///
+    /// Unlike [finalize](LayerExt::finalize), which leaves the checksum untouched when the
+    /// previous layer is not [Ipv4] or [Ipv6], this returns a [LayerError::Finalize] since
+    /// the pseudo-header required for the checksum is unavailable.
+    pub fn finalize_strict(
+        &mut self,
+        prev: &[LayerOwned],
+        next: &[LayerOwned],
+    ) -> Result<(), LayerError> {
+        let has_ip = matches!(
+            prev.last(),
+            Some(l) if get_layer!(l, Ipv4).is_some() || get_layer!(l, Ipv6).is_some()
+        );
+
+        if !has_ip {
+            return Err(LayerError::Finalize(
+                "Udp checksum requires a preceding Ipv4 or Ipv6 layer".to_string(),
+            ));
+        }
 
-            // Clear checksum bytes for calculation
-            data[6] = 0x00;
-            data[7] = 0x00;
+        LayerExt::finalize(self, prev, next)
+    }
+}
 
-            data
-        };
-        let udp_header_len = udp_header.len();
+impl Udp {
+    /// Parse a udp layer, validating the length field against the input
+    ///
+    /// Unlike [parse](LayerExt::parse), this returns a [LayerError::Validate] if the length
+    /// is less than the header length, see [validate](LayerExt::validate), or exceeds the
+    /// length of the input.
+    pub fn parse_strict(input: &[u8]) -> Result<(&[u8], Self), LayerError> {
+        let (rest, udp) = Self::parse(input)?;
+        udp.validate()?;
+
+        let available = input.len();
+        if usize::from(udp.length) > available {
+            return Err(LayerError::Validate(format!(
+                "udp length {} exceeds available {} bytes",
+                udp.length, available
+            )));
+        }
 
-        let udp_payload = crate::layer::utils::layers_to_bytes(next)?;
+        Ok((rest, udp))
+    }
+}
+
+impl Layer for Udp {}
+impl LayerExt for Udp {
+    /// Update the length and checksum fields
+    ///
+    /// The checksum is computed over the Ipv4 or Ipv6 pseudo-header, it is left as-is if the
+    /// previous layer is not an ip layer. See [Udp::finalize_strict] to error instead.
+    fn finalize(&mut self, prev: &[LayerOwned], next: &[LayerOwned]) -> Result<(), LayerError> {
+        let mut payload_checksum = Checksum::new();
 
         // length of udp header + udp_payload
-        let udp_length = udp_header_len
-            .checked_add(udp_payload.len())
-            .ok_or_else(|| {
+        let mut udp_length = UDP_HEADER_LENGTH;
+        for layer in next {
+            let data = layer.to_bytes()?;
+            udp_length = udp_length.checked_add(data.len()).ok_or_else(|| {
                 LayerError::Finalize(
                     "Overflow occured when calculating length for udp (v4) checksum".to_string(),
                 )
             })?;
+            payload_checksum.add_bytes(&data);
+        }
 
+        // The length is covered by the checksum, update it before serializing the header
         self.length = u16::try_from(udp_length).map_err(|_e| {
             LayerError::Finalize(format!("Invalid Udp length {} > {}", udp_length, u16::MAX))
         })?;
 
+        let udp_header = {
+            let mut data = LayerExt::to_bytes(self)?;
+
+            // Clear checksum bytes for calculation
+            data[6] = 0x00;
+            data[7] = 0x00;
+
+            data
+        };
+
+        let mut udp_checksum = Checksum::new();
+        udp_checksum.add_bytes(&udp_header);
+        udp_checksum.merge(&payload_checksum);
+
         // Update the udp checksum
         if let Some(prev_layer) = prev.last() {
             let ip_pseudo_header = if let Some(ipv4) = get_layer!(prev_layer, Ipv4) {
@@ -137,6 +176,7 @@ impl LayerExt for Udp {
                 Some(
                     Ipv6PseudoHeader::new(
                         ipv6,
+                        ipv6.upper_protocol(),
                         u32::try_from(udp_length).map_err(|_e| {
                             LayerError::Finalize("Failed to convert udp_length to u32".to_string())
                         })?,
@@ -148,11 +188,11 @@ impl LayerExt for Udp {
             };
 
             if let Some(ip_pseudo_header) = ip_pseudo_header {
-                let mut data = ip_pseudo_header;
-                data.extend(udp_header);
-                data.extend(udp_payload);
+                let mut checksum = Checksum::new();
+                checksum.add_bytes(&ip_pseudo_header);
+                checksum.merge(&udp_checksum);
 
-                self.checksum = super::ip::checksum(&data)
+                self.checksum = checksum.finish()
             }
         }
 
@@ -171,12 +211,36 @@ impl LayerExt for Udp {
     fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
         Ok(DekuContainerWrite::to_bytes(self)?)
     }
+
+    fn length_field(&self) -> Option<LengthField> {
+        Some(LengthField {
+            name: "length",
+            value: self.length,
+            includes_header: true,
+            extensions_length: 0,
+        })
+    }
+
+    /// Validate the length covers at least the udp header
+    fn validate(&self) -> Result<(), LayerError> {
+        if self.length < 8 {
+            return Err(LayerError::Validate(format!(
+                "invalid udp length {}",
+                self.length
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::layer::ip::{Ipv4, Ipv6};
+    use crate::layer::{
+        ip::{Ipv4, Ipv6},
+        raw::Raw,
+    };
     use hexlit::hex;
     use rstest::*;
     use std::convert::TryFrom;
@@ -255,7 +319,7 @@ mod tests {
 
     #[test]
     fn test_udp_finalize_checksum_v4() {
-        let expected_checksum = 0x0127;
+        let expected_checksum = 0x0057;
 
         let ipv4 = Box::new(Ipv4::default());
 
@@ -270,9 +334,40 @@ mod tests {
         assert_eq!(expected_checksum, udp.checksum);
     }
 
+    #[rstest(
+        ip_layer,
+        case::v4(Box::new(Ipv4::default())),
+        case::v6(Box::new(Ipv6::default()))
+    )]
+    fn test_udp_finalize_checksum_split_payload(ip_layer: LayerOwned) {
+        let raw = |data: &[u8]| -> LayerOwned {
+            Box::new(Raw {
+                data: data.to_vec(),
+                bit_offset: 0,
+            })
+        };
+
+        // Checksum over a payload split in odd sized layers
+        let mut udp = Udp::default();
+        udp.finalize(
+            core::slice::from_ref(&ip_layer),
+            &[raw(b"abc"), raw(b"defgh"), raw(b"i")],
+        )
+        .unwrap();
+
+        // Checksum over the concatenated payload
+        let mut expected_udp = Udp::default();
+        expected_udp
+            .finalize(&[ip_layer], &[raw(b"abcdefghi")])
+            .unwrap();
+
+        assert_ne!(0, udp.checksum);
+        assert_eq!(expected_udp.checksum, udp.checksum);
+    }
+
     #[test]
     fn test_udp_finalize_checksum_v6() {
-        let expected_checksum = 0x00F3;
+        let expected_checksum = 0x0023;
 
         let ipv6 = Box::new(Ipv6::default());
 
@@ -303,6 +398,48 @@ mod tests {
         assert_eq!(expected_length, udp.length);
     }
 
+    #[test]
+    fn test_udp_finalize_checksum_v6_routing() {
+        use crate::layer::ip::ipv6::{Ipv6Extension, Ipv6Routing, Ipv6RoutingData};
+
+        let final_destination = 0x20010db8000000000000000000000002;
+
+        // Ipv6 / SRH, the destination address is the next segment
+        let routed: LayerOwned = Box::new(Ipv6 {
+            next_header: IpProtocol::IPV6ROUTE,
+            dst: 0x20010db8000000000000000000000001,
+            extensions: vec![Ipv6Extension::Routing(Ipv6Routing {
+                next_header: IpProtocol::UDP,
+                length: 4,
+                routing_type: 4,
+                segments_left: 1,
+                data: Ipv6RoutingData::Srh {
+                    last_entry: 1,
+                    flags: 0,
+                    tag: 0,
+                    segments: vec![final_destination, 0x20010db8000000000000000000000001],
+                    tlvs: vec![],
+                },
+            })],
+            ..Default::default()
+        });
+
+        // Ipv6 arrived at the final destination
+        let direct: LayerOwned = Box::new(Ipv6 {
+            next_header: IpProtocol::UDP,
+            dst: final_destination,
+            ..Default::default()
+        });
+
+        let mut udp = Udp::default();
+        udp.finalize(&[routed], &[]).unwrap();
+
+        let mut expected_udp = Udp::default();
+        expected_udp.finalize(&[direct], &[]).unwrap();
+
+        assert_eq!(expected_udp.checksum, udp.checksum);
+    }
+
     #[test]
     fn test_udp_finalize() {
         let mut udp = Udp::default();
@@ -314,11 +451,73 @@ mod tests {
 
         // Only these fields should change during a finalize
         let expected_udp = Udp {
-            checksum: 0x018B,
+            checksum: 0x011F,
             length: 108,
             ..Default::default()
         };
 
         assert_eq!(expected_udp, udp);
     }
+
+    #[test]
+    fn test_udp_finalize_standalone() {
+        let mut udp = Udp {
+            checksum: 0xAABB,
+            ..Default::default()
+        };
+
+        // No preceding ip layer, the checksum is left as-is
+        udp.finalize(&[], &[Layer100::boxed()]).unwrap();
+
+        let expected_udp = Udp {
+            checksum: 0xAABB,
+            length: 108,
+            ..Default::default()
+        };
+        assert_eq!(expected_udp, udp);
+    }
+
+    #[rstest(length, expected,
+        case::header(8, Ok(())),
+        case::payload(108, Ok(())),
+        case::invalid(7, Err(LayerError::Validate("invalid udp length 7".to_string()))),
+    )]
+    fn test_udp_validate(length: u16, expected: Result<(), LayerError>) {
+        let udp = Udp {
+            length,
+            ..Default::default()
+        };
+        assert_eq!(expected, udp.validate());
+    }
+
+    #[rstest(input, expected,
+        case::header(&hex!("ff02ff3500080000"), Ok(())),
+        case::payload(&hex!("ff02ff35000a0000 aabb"), Ok(())),
+        case::short(&hex!("ff02ff3500040000"), Err(LayerError::Validate("invalid udp length 4".to_string()))),
+        case::truncated(
+            &hex!("ff02ff35000b0000 aabb"),
+            Err(LayerError::Validate("udp length 11 exceeds available 10 bytes".to_string()))
+        ),
+    )]
+    fn test_udp_parse_strict(input: &[u8], expected: Result<(), LayerError>) {
+        assert_eq!(expected, Udp::parse_strict(input).map(|_| ()));
+        assert!(Udp::parse(input).is_ok());
+    }
+
+    #[test]
+    fn test_udp_finalize_strict() {
+        let mut udp = Udp::default();
+        assert!(matches!(
+            udp.finalize_strict(&[], &[Layer100::boxed()]),
+            Err(LayerError::Finalize(_))
+        ));
+        assert!(matches!(
+            udp.finalize_strict(&[Layer100::boxed()], &[Layer100::boxed()]),
+            Err(LayerError::Finalize(_))
+        ));
+
+        let ipv4 = Box::new(Ipv4::default());
+        udp.finalize_strict(&[ipv4], &[Layer100::boxed()]).unwrap();
+        assert_eq!(0x011F, udp.checksum);
+    }
 }
@@ -0,0 +1,102 @@
+/*!
+VXLAN layer
+
+Virtual eXtensible Local Area Network, carrying Ethernet frames over [Udp](crate::layer::udp::Udp)
+*/
+
+use crate::layer::{Layer, LayerError, LayerExt, LayerOwned};
+use alloc::{format, vec::Vec};
+use deku::prelude::*;
+
+/// Udp destination port assigned to VXLAN
+pub const VXLAN_PORT: u16 = 4789;
+
+/// Length of the VXLAN header
+pub const VXLAN_HEADER_LENGTH: usize = 8;
+
+/// Flag set when the VNI is valid
+pub const VXLAN_FLAG_I: u8 = 0x08;
+
+/**
+VXLAN Header
+
+```text
+ 0                   1                   2                   3
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|R|R|R|R|I|R|R|R|            Reserved                           |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|                VXLAN Network Identifier (VNI) |   Reserved    |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+```
+*/
+#[derive(Debug, PartialEq, Clone, DekuRead, DekuWrite)]
+#[deku(endian = "big")]
+pub struct Vxlan {
+    /// Flags, the I flag ([VXLAN_FLAG_I]) is set for a valid VNI
+    pub flags: u8,
+    /// Reserved
+    #[deku(bits = "24")]
+    pub reserved0: u32,
+    /// VXLAN Network Identifier
+    #[deku(bits = "24")]
+    pub vni: u32,
+    /// Reserved
+    pub reserved1: u8,
+}
+
+impl Default for Vxlan {
+    fn default() -> Self {
+        Vxlan {
+            flags: VXLAN_FLAG_I,
+            reserved0: 0,
+            vni: 0,
+            reserved1: 0,
+        }
+    }
+}
+
+impl Layer for Vxlan {}
+impl LayerExt for Vxlan {
+    fn finalize(&mut self, _prev: &[LayerOwned], _next: &[LayerOwned]) -> Result<(), LayerError> {
+        Ok(())
+    }
+
+    fn parse(input: &[u8]) -> Result<(&[u8], Self), LayerError>
+    where
+        Self: Sized,
+    {
+        let ((rest, bit_offset), vxlan) = Vxlan::from_bytes((input, 0))?;
+        debug_assert_eq!(0, bit_offset);
+        Ok((rest, vxlan))
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
+        Ok(DekuContainerWrite::to_bytes(self)?)
+    }
+
+    fn length(&self) -> Result<usize, LayerError> {
+        Ok(VXLAN_HEADER_LENGTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hexlit::hex;
+    use rstest::*;
+    use std::convert::TryFrom;
+
+    #[rstest(input, expected,
+        case(&hex!("0800000000007b00"), Vxlan { vni: 123, ..Default::default() }),
+        case(&hex!("08000000ffffff00"), Vxlan { vni: 0xffffff, ..Default::default() }),
+    )]
+    fn test_vxlan_rw(input: &[u8], expected: Vxlan) {
+        let ret_read = Vxlan::try_from(input).unwrap();
+        assert_eq!(expected, ret_read);
+
+        let ret_write = LayerExt::to_bytes(&ret_read).unwrap();
+        assert_eq!(input.to_vec(), ret_write);
+        assert_eq!(ret_write.len(), ret_read.length().unwrap());
+    }
+}
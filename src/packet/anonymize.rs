@@ -0,0 +1,286 @@
+/*!
+Address anonymization
+
+An [AnonScheme] rewrites addresses deterministically given a key, such that the same
+address is anonymized the same way across packets and captures.
+
+Note: the keyed function used is a fast non-cryptographic mix, the scheme is meant to
+prevent casual identification of hosts in shared captures, not to resist a determined
+attacker.
+*/
+use crate::layer::{
+    ether::MacAddress,
+    gre::Gre,
+    icmp::Icmp6,
+    ip::{Checksum, Ipv4, Ipv6},
+    tcp::Tcp,
+    udp::Udp,
+    LayerError, LayerOwned,
+};
+use crate::{get_layer, is_layer};
+use alloc::boxed::Box;
+
+/// Address anonymization scheme, see [Packet::anonymize](crate::packet::Packet::anonymize)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum AnonScheme {
+    /// Prefix-preserving anonymization
+    ///
+    /// Two ip addresses sharing a prefix of `n` bits are anonymized to addresses sharing a
+    /// prefix of exactly `n` bits, keeping the subnet structure. Mac addresses are replaced
+    /// by a keyed hash, keeping the multicast bit. The broadcast mac address is unchanged.
+    PrefixPreserving {
+        /// Key of the anonymization
+        key: u64,
+    },
+}
+
+/// splitmix64 finalizer
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Keyed pseudo-random function of a bit string of `len` bits
+fn prf(key: u64, len: u32, value: u128) -> u64 {
+    let hi = (value >> 64) as u64;
+    let lo = value as u64;
+    mix(mix(mix(key ^ u64::from(len)) ^ hi) ^ lo)
+}
+
+/// Prefix-preserving permutation of the `bits` least significant bits of `addr`
+///
+/// Each output bit is the input bit flipped by a function of the key and the preceding bits.
+fn prefix_preserving(key: u64, addr: u128, bits: u32) -> u128 {
+    let mut output = 0u128;
+    for i in 0..bits {
+        let prefix = addr.checked_shr(bits - i).unwrap_or(0);
+        let bit = (addr >> (bits - 1 - i)) & 1;
+        let flip = u128::from(prf(key, i, prefix) & 1);
+
+        output = (output << 1) | (bit ^ flip);
+    }
+
+    output
+}
+
+impl AnonScheme {
+    /// Anonymize an ipv4 address
+    pub fn anonymize_ipv4(&self, addr: u32) -> u32 {
+        match self {
+            AnonScheme::PrefixPreserving { key } => {
+                prefix_preserving(*key, u128::from(addr), 32) as u32
+            }
+        }
+    }
+
+    /// Anonymize an ipv6 address
+    pub fn anonymize_ipv6(&self, addr: u128) -> u128 {
+        match self {
+            AnonScheme::PrefixPreserving { key } => prefix_preserving(*key, addr, 128),
+        }
+    }
+
+    /// Anonymize a mac address
+    pub fn anonymize_mac(&self, mac: &MacAddress) -> MacAddress {
+        if mac.0 == [0xff; 6] {
+            return mac.clone();
+        }
+
+        match self {
+            AnonScheme::PrefixPreserving { key } => {
+                let mut value = [0u8; 16];
+                value[10..].copy_from_slice(&mac.0);
+                let hash = prf(*key, 48, u128::from_be_bytes(value)).to_be_bytes();
+
+                let mut output = [0u8; 6];
+                output.copy_from_slice(&hash[2..]);
+
+                // keep the individual/group bit
+                output[0] = (output[0] & !0x01) | (mac.0[0] & 0x01);
+
+                MacAddress(output)
+            }
+        }
+    }
+}
+
+/// Checksum field of a layer, `None` if the layer has no checksum or the checksum is unused
+fn checksum_of(layer: &LayerOwned) -> Option<u16> {
+    if let Some(ipv4) = get_layer!(layer, Ipv4) {
+        Some(ipv4.checksum)
+    } else if let Some(tcp) = get_layer!(layer, Tcp) {
+        Some(tcp.checksum)
+    } else if let Some(udp) = get_layer!(layer, Udp) {
+        // a zero udp checksum is not computed
+        Some(udp.checksum).filter(|checksum| *checksum != 0)
+    } else if let Some(icmp6) = get_layer!(layer, Icmp6) {
+        Some(icmp6.checksum)
+    } else if let Some(gre) = get_layer!(layer, Gre) {
+        gre.checksum
+    } else {
+        None
+    }
+}
+
+/// Copy of a layer with its checksum field set to `checksum`, see [checksum_of]
+fn with_checksum(layer: &LayerOwned, checksum: u16) -> LayerOwned {
+    if let Some(ipv4) = get_layer!(layer, Ipv4) {
+        Box::new(Ipv4 {
+            checksum,
+            ..ipv4.clone()
+        })
+    } else if let Some(tcp) = get_layer!(layer, Tcp) {
+        Box::new(Tcp {
+            checksum,
+            ..tcp.clone()
+        })
+    } else if let Some(udp) = get_layer!(layer, Udp) {
+        // zero means no checksum, its one's complement equivalent is used instead
+        let checksum = if checksum == 0 { 0xffff } else { checksum };
+        Box::new(Udp {
+            checksum,
+            ..udp.clone()
+        })
+    } else if let Some(icmp6) = get_layer!(layer, Icmp6) {
+        Box::new(Icmp6 {
+            checksum,
+            ..icmp6.clone()
+        })
+    } else if let Some(gre) = get_layer!(layer, Gre) {
+        Box::new(Gre {
+            checksum: Some(checksum),
+            ..gre.clone()
+        })
+    } else {
+        layer.clone()
+    }
+}
+
+/// Sum of the data covered by the checksum of `layers[index]`
+///
+/// The ipv4 checksum covers its header. Other checksums cover their layer and the following
+/// layers, preceded by the addresses of the ip pseudo header for [Tcp], [Udp] and [Icmp6].
+/// The remaining pseudo header fields are unchanged by anonymization and left out.
+fn covered_sum(layers: &[LayerOwned], index: usize) -> Result<Checksum, LayerError> {
+    let mut sum = Checksum::new();
+
+    let layer = &layers[index];
+    if is_layer!(layer, Ipv4) {
+        sum.add_bytes(&layer.to_bytes()?);
+        return Ok(sum);
+    }
+
+    if is_layer!(layer, Tcp) || is_layer!(layer, Udp) || is_layer!(layer, Icmp6) {
+        if let Some(prev) = index.checked_sub(1).map(|prev| &layers[prev]) {
+            if let Some(ipv4) = get_layer!(prev, Ipv4) {
+                sum.add_bytes(&ipv4.src.to_be_bytes());
+                sum.add_bytes(&ipv4.dst.to_be_bytes());
+            } else if let Some(ipv6) = get_layer!(prev, Ipv6) {
+                sum.add_bytes(&ipv6.src.to_be_bytes());
+                sum.add_bytes(&ipv6.final_destination().to_be_bytes());
+            }
+        }
+    }
+
+    for layer in &layers[index..] {
+        sum.add_bytes(&layer.to_bytes()?);
+    }
+
+    Ok(sum)
+}
+
+/// Update the checksums of `layers` for the changes made to the `original` layers
+///
+/// Each checksum is adjusted by the difference of the data it covers, per
+/// [RFC1624](https://datatracker.ietf.org/doc/html/rfc1624), starting from the innermost
+/// layer. Unlike [finalize](crate::packet::Packet::finalize) no length field is rewritten,
+/// and checksums stay consistent with payloads missing from the capture, such as truncated
+/// packets.
+pub(crate) fn update_checksums(
+    original: &[LayerOwned],
+    layers: &mut [LayerOwned],
+) -> Result<(), LayerError> {
+    debug_assert_eq!(original.len(), layers.len());
+
+    for index in (0..layers.len()).rev() {
+        let checksum = match checksum_of(&layers[index]) {
+            Some(checksum) => checksum,
+            None => continue,
+        };
+
+        // the checksum fields are equal in both, as they are adjusted from the innermost layer
+        let old = covered_sum(original, index)?;
+        let new = covered_sum(layers, index)?;
+        if old.finish() == new.finish() {
+            continue;
+        }
+
+        // HC' = ~(~HC + ~m + m')
+        let mut adjusted = Checksum::new();
+        adjusted.add_bytes(&(!checksum).to_be_bytes());
+        adjusted.add_bytes(&old.finish().to_be_bytes());
+        adjusted.add_bytes(&(!new.finish()).to_be_bytes());
+
+        layers[index] = with_checksum(&layers[index], adjusted.finish());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEME: AnonScheme = AnonScheme::PrefixPreserving { key: 0x1234 };
+
+    #[test]
+    fn test_anonymize_ipv4_prefix() {
+        let a = SCHEME.anonymize_ipv4(0xc0a8010a); // 192.168.1.10
+        let b = SCHEME.anonymize_ipv4(0xc0a80114); // 192.168.1.20
+        let c = SCHEME.anonymize_ipv4(0x0a000001); // 10.0.0.1
+
+        assert_ne!(0xc0a8010a, a);
+        assert_eq!(a, SCHEME.anonymize_ipv4(0xc0a8010a));
+
+        // common prefix length is preserved
+        assert_eq!(
+            (0xc0a8010au32 ^ 0xc0a80114).leading_zeros(),
+            (a ^ b).leading_zeros()
+        );
+        assert_eq!(
+            (0xc0a8010au32 ^ 0x0a000001).leading_zeros(),
+            (a ^ c).leading_zeros()
+        );
+
+        // different keys give different addresses
+        let other = AnonScheme::PrefixPreserving { key: 0x5678 };
+        assert_ne!(a, other.anonymize_ipv4(0xc0a8010a));
+    }
+
+    #[test]
+    fn test_anonymize_ipv6_prefix() {
+        let a = SCHEME.anonymize_ipv6(0x20010db8000000000000000000000001);
+        let b = SCHEME.anonymize_ipv6(0x20010db8000000000000000000000002);
+
+        assert_ne!(0x20010db8000000000000000000000001, a);
+        assert_eq!(126, (a ^ b).leading_zeros());
+    }
+
+    #[test]
+    fn test_anonymize_mac() {
+        let mac = MacAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        let anon = SCHEME.anonymize_mac(&mac);
+        assert_ne!(mac, anon);
+        assert_eq!(anon, SCHEME.anonymize_mac(&mac));
+        assert_eq!(0, anon.0[0] & 0x01);
+
+        let multicast = MacAddress([0x01, 0x00, 0x5e, 0x00, 0x00, 0x01]);
+        assert_eq!(1, SCHEME.anonymize_mac(&multicast).0[0] & 0x01);
+
+        let broadcast = MacAddress([0xff; 6]);
+        assert_eq!(broadcast, SCHEME.anonymize_mac(&broadcast));
+    }
+}
@@ -9,31 +9,175 @@ Documentation only module, listing the default layer bindings for [PacketParser]
 |-----------|------------------|------------
 | [Ether] | type == Ipv4 | [Ipv4]
 | [Ether] | type == Ipv6 | [Ipv4]
+| [Ether] | type is an 802.3 length | [Llc]
+| [Llc] | SNAP service access points | [Snap]
+| [Snap] | OUI == 00000C and protocol id == 0x2000 | [Cdp]
 | [Ipv4] | protocol == Tcp | [Tcp]
 | [Ipv4] | protocol == Udp | [Udp]
 | [Ipv4] | protocol == Icmp | [Icmp4]
-| [Ipv6] | protocol == Tcp | [Tcp]
-| [Ipv6] | protocol == Udp | [Udp]
+| [Ipv4] | protocol == Gre | [Gre]
+| [Ipv6] | upper protocol == Tcp | [Tcp]
+| [Ipv6] | upper protocol == Udp | [Udp]
+| [Ipv6] | upper protocol == Icmp6 | [Icmp6]
+| [Ipv6] | upper protocol == Gre | [Gre]
+| [Udp] | port == 53 | [Dns]
+| [Tcp] | port == 53 | [DnsTcp]
+| [Udp] | destination port == 4789 and I flag set | [Vxlan]
+| [Vxlan] | | [Ether]
+| [Gre] | protocol == Ipv4 | [Ipv4]
+| [Gre] | protocol == Ipv6 | [Ipv6]
 
+Remaining data not matched by a binding is parsed as [Raw], see
+[set_default_parser](crate::packet::PacketParser::set_default_parser).
+
+[Raw]: crate::layer::raw::Raw
 [Ether]: crate::layer::ether::Ether
 [Ipv4]: crate::layer::ip::Ipv4
 [Ipv6]: crate::layer::ip::Ipv6
 [Udp]: crate::layer::udp::Udp
 [Tcp]: crate::layer::tcp::Tcp
 [Icmp]: crate::layer::icmp::Icmp4
+[Icmp6]: crate::layer::icmp::Icmp6
+[Dns]: crate::layer::dns::Dns
+[DnsTcp]: crate::layer::dns::DnsTcp
+[Vxlan]: crate::layer::vxlan::Vxlan
+[Gre]: crate::layer::gre::Gre
+[Llc]: crate::layer::llc::Llc
+[Snap]: crate::layer::llc::Snap
+[Cdp]: crate::layer::cdp::Cdp
 */
 use crate::{
     layer::{
+        cdp::{Cdp, CDP_SNAP_OUI, CDP_SNAP_PROTOCOL_ID},
+        dns::{Dns, DnsTcp, DNS_HEADER_LENGTH},
         ether::{Ether, EtherType},
-        icmp::Icmp4,
+        gre::Gre,
+        icmp::{Icmp4, Icmp6},
         ip::{IpProtocol, Ipv4, Ipv6},
+        llc::{Llc, Snap},
         raw::Raw,
         tcp::Tcp,
         udp::Udp,
-        LayerExt,
+        vxlan::{Vxlan, VXLAN_FLAG_I, VXLAN_HEADER_LENGTH, VXLAN_PORT},
+        LayerExt, LayerOwned,
     },
     packet::PacketParser,
 };
+use alloc::{boxed::Box, vec::Vec};
+use core::any::TypeId;
+
+const DNS_PORT: u16 = 53;
+
+/// Layer carrying another layer in the default bindings
+///
+/// Holds the type of the carried layer, the type of the carrying layer and a builder of the
+/// carrying layer, with the fields selecting the carried layer set.
+type Carrier = (TypeId, TypeId, fn() -> LayerOwned);
+
+/// Carriers of the layers of the default bindings, see [lower_layers]
+///
+/// Keep in sync with the bindings of [create_packetparser].
+fn carriers() -> [Carrier; 13] {
+    [
+        (TypeId::of::<Ipv4>(), TypeId::of::<Ether>(), || {
+            Box::new(Ether::default())
+        }),
+        (TypeId::of::<Ipv6>(), TypeId::of::<Ether>(), || {
+            Box::new(Ether {
+                ether_type: EtherType::IPv6,
+                ..Default::default()
+            })
+        }),
+        (TypeId::of::<Llc>(), TypeId::of::<Ether>(), || {
+            // the 802.3 length is updated on finalize
+            Box::new(Ether {
+                ether_type: EtherType::Unknown(0),
+                ..Default::default()
+            })
+        }),
+        (TypeId::of::<Snap>(), TypeId::of::<Llc>(), || {
+            Box::new(Llc::default())
+        }),
+        (TypeId::of::<Cdp>(), TypeId::of::<Snap>(), || {
+            Box::new(Snap {
+                oui: CDP_SNAP_OUI,
+                protocol_id: CDP_SNAP_PROTOCOL_ID,
+            })
+        }),
+        (TypeId::of::<Tcp>(), TypeId::of::<Ipv4>(), || {
+            Box::new(Ipv4 {
+                protocol: IpProtocol::TCP,
+                ..Default::default()
+            })
+        }),
+        (TypeId::of::<Udp>(), TypeId::of::<Ipv4>(), || {
+            Box::new(Ipv4 {
+                protocol: IpProtocol::UDP,
+                ..Default::default()
+            })
+        }),
+        (TypeId::of::<Icmp4>(), TypeId::of::<Ipv4>(), || {
+            Box::new(Ipv4 {
+                protocol: IpProtocol::ICMP,
+                ..Default::default()
+            })
+        }),
+        (TypeId::of::<Gre>(), TypeId::of::<Ipv4>(), || {
+            Box::new(Ipv4 {
+                protocol: IpProtocol::GRE,
+                ..Default::default()
+            })
+        }),
+        (TypeId::of::<Icmp6>(), TypeId::of::<Ipv6>(), || {
+            Box::new(Ipv6 {
+                version: 6,
+                next_header: IpProtocol::IPV6ICMP,
+                ..Default::default()
+            })
+        }),
+        (TypeId::of::<Dns>(), TypeId::of::<Udp>(), || {
+            Box::new(Udp {
+                dport: DNS_PORT,
+                ..Default::default()
+            })
+        }),
+        (TypeId::of::<Vxlan>(), TypeId::of::<Udp>(), || {
+            Box::new(Udp {
+                dport: VXLAN_PORT,
+                ..Default::default()
+            })
+        }),
+        (TypeId::of::<DnsTcp>(), TypeId::of::<Tcp>(), || {
+            Box::new(Tcp {
+                dport: DNS_PORT,
+                ..Default::default()
+            })
+        }),
+    ]
+}
+
+/// Lower layers carrying a layer of type `tid` in the default bindings, outermost first
+///
+/// Used by [Packet::template_for](crate::packet::Packet::template_for). [Ether] is the
+/// outermost layer.
+pub(crate) fn lower_layers(tid: TypeId) -> Vec<LayerOwned> {
+    let carriers = carriers();
+
+    let mut layers = Vec::new();
+    let mut tid = tid;
+    while tid != TypeId::of::<Ether>() {
+        match carriers.iter().find(|(carried, _, _)| *carried == tid) {
+            Some((_, carrier, build)) => {
+                layers.push(build());
+                tid = *carrier;
+            }
+            None => break,
+        }
+    }
+
+    layers.reverse();
+    layers
+}
 
 /// Create a [PacketParser](crate::packet::PacketParser) with a set of bindings using layers
 /// defined in the crate
@@ -43,24 +187,98 @@ pub(crate) fn create_packetparser() -> PacketParser {
     pb.bind_layer(|ether: &Ether, _rest| match ether.ether_type {
         EtherType::IPv4 => Some(Ipv4::parse_layer),
         EtherType::IPv6 => Some(Ipv6::parse_layer),
-        _ => Some(Raw::parse_layer),
+        _ if ether.is_8023() => Some(Llc::parse_layer),
+        _ => None,
+    });
+
+    pb.bind_layer(|llc: &Llc, _rest| {
+        if llc.is_snap() {
+            Some(Snap::parse_layer)
+        } else {
+            None
+        }
+    });
+
+    // Only parse valid messages, such that frames padded to the minimum length are left to
+    // the default parser
+    pb.bind_layer(|snap: &Snap, rest| {
+        if snap.oui == CDP_SNAP_OUI
+            && snap.protocol_id == CDP_SNAP_PROTOCOL_ID
+            && Cdp::parse(rest).is_ok()
+        {
+            Some(Cdp::parse_layer)
+        } else {
+            None
+        }
     });
 
     pb.bind_layer(|ipv4: &Ipv4, _rest| match ipv4.protocol {
         IpProtocol::TCP => Some(Tcp::parse_layer),
         IpProtocol::UDP => Some(Udp::parse_layer),
         IpProtocol::ICMP => Some(Icmp4::parse_layer),
-        _ => Some(Raw::parse_layer),
+        IpProtocol::GRE => Some(Gre::parse_layer),
+        _ => None,
     });
 
-    pb.bind_layer(|ipv6: &Ipv6, _rest| match ipv6.next_header {
+    pb.bind_layer(|ipv6: &Ipv6, _rest| match ipv6.upper_protocol() {
         IpProtocol::TCP => Some(Tcp::parse_layer),
         IpProtocol::UDP => Some(Udp::parse_layer),
-        _ => Some(Raw::parse_layer),
+        IpProtocol::IPV6ICMP => Some(Icmp6::parse_layer),
+        IpProtocol::GRE => Some(Gre::parse_layer),
+        _ => None,
+    });
+
+    // Only parse valid messages, other port 53 payloads are left to the default parser.
+    // Validating parses the message once more before `Dns::parse_layer` runs, which is only
+    // paid for port 53 traffic.
+    pb.bind_layer(|udp: &Udp, rest| {
+        if (udp.sport == DNS_PORT || udp.dport == DNS_PORT)
+            && rest.len() >= DNS_HEADER_LENGTH
+            && Dns::parse(rest).is_ok()
+        {
+            Some(Dns::parse_layer)
+        } else {
+            None
+        }
+    });
+
+    // Only parse headers with a valid VNI carrying an ethernet frame, other port 4789
+    // payloads are left to the default parser
+    pb.bind_layer(|udp: &Udp, rest| {
+        if udp.dport == VXLAN_PORT
+            && rest.len() >= VXLAN_HEADER_LENGTH
+            && rest[0] & VXLAN_FLAG_I != 0
+            && Ether::parse(&rest[VXLAN_HEADER_LENGTH..]).is_ok()
+        {
+            Some(Vxlan::parse_layer)
+        } else {
+            None
+        }
+    });
+    pb.bind_layer(|_vxlan: &Vxlan, _rest| Some(Ether::parse_layer));
+
+    pb.bind_layer(|gre: &Gre, _rest| match gre.protocol {
+        EtherType::IPv4 => Some(Ipv4::parse_layer),
+        EtherType::IPv6 => Some(Ipv6::parse_layer),
+        _ => None,
+    });
+
+    // Only parse complete and valid messages, a message can span multiple segments and other
+    // port 53 payloads are left to the default parser
+    pb.bind_layer(|tcp: &Tcp, rest| {
+        let complete = rest.len() >= 2
+            && rest.len() - 2 >= usize::from(u16::from_be_bytes([rest[0], rest[1]]));
+        if (tcp.sport == DNS_PORT || tcp.dport == DNS_PORT)
+            && complete
+            && DnsTcp::parse(rest).is_ok()
+        {
+            Some(DnsTcp::parse_layer)
+        } else {
+            None
+        }
     });
 
-    pb.bind_layer(|_tcp: &Tcp, _rest| Some(Raw::parse_layer));
-    pb.bind_layer(|_udp: &Udp, _rest| Some(Raw::parse_layer));
+    pb.set_default_parser(Raw::parse_layer);
 
     pb
 }
@@ -0,0 +1,305 @@
+/*!
+Packet parser configuration
+
+A [ParserConfig] describes [PacketParser](crate::packet::PacketParser) bindings as data,
+allowing the bindings to be defined at runtime, for example from a configuration file.
+With the `serde` feature enabled, [ParserConfig] can be deserialized.
+
+Layers are referred to by name. A binding matches a field of the source layer against
+a value and selects the next layer.
+
+| Name | Layer | Matched field
+|-----------|------------------|------------
+| `ether` | [Ether] | ether type
+| `ipv4` | [Ipv4] | protocol
+| `ipv6` | [Ipv6] | upper layer protocol
+| `gre` | [Gre] | protocol type
+| `tcp` | [Tcp] | source or destination port
+| `udp` | [Udp] | source or destination port
+| `icmp4` | [Icmp4] |
+| `icmp6` | [Icmp6] |
+| `cdp` | [Cdp] |
+| `dns` | [Dns] |
+| `dns_tcp` | [DnsTcp] |
+| `vxlan` | [Vxlan] |
+| `raw` | [Raw] |
+
+[Ether]: crate::layer::ether::Ether
+[Ipv4]: crate::layer::ip::Ipv4
+[Ipv6]: crate::layer::ip::Ipv6
+[Udp]: crate::layer::udp::Udp
+[Tcp]: crate::layer::tcp::Tcp
+[Icmp4]: crate::layer::icmp::Icmp4
+[Icmp6]: crate::layer::icmp::Icmp6
+[Cdp]: crate::layer::cdp::Cdp
+[Dns]: crate::layer::dns::Dns
+[DnsTcp]: crate::layer::dns::DnsTcp
+[Vxlan]: crate::layer::vxlan::Vxlan
+[Gre]: crate::layer::gre::Gre
+[Raw]: crate::layer::raw::Raw
+*/
+use crate::{
+    layer::{
+        cdp::Cdp,
+        dns::{Dns, DnsTcp},
+        ether::{Ether, EtherType},
+        gre::Gre,
+        icmp::{Icmp4, Icmp6},
+        ip::{IpProtocol, Ipv4, Ipv6},
+        raw::Raw,
+        tcp::Tcp,
+        udp::Udp,
+        vxlan::Vxlan,
+        LayerExt,
+    },
+    packet::{LayerParser, PacketError, PacketParser},
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::convert::TryFrom;
+use deku::prelude::*;
+
+/// Set of bindings used to create a [PacketParser](crate::packet::PacketParser)
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParserConfig {
+    /// Layer bindings, executed in reverse order like [PacketParser::bind_layer](crate::packet::PacketParser::bind_layer)
+    pub bindings: Vec<BindingConfig>,
+}
+
+/// A single layer binding
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BindingConfig {
+    /// Name of the layer the binding applies to
+    pub layer: String,
+    /// Value of the field to match
+    pub value: u16,
+    /// Name of the next layer
+    pub next: String,
+}
+
+impl BindingConfig {
+    /// Create a binding from `layer` to `next` when the field matches `value`
+    pub fn new(layer: &str, value: u16, next: &str) -> Self {
+        Self {
+            layer: layer.to_string(),
+            value,
+            next: next.to_string(),
+        }
+    }
+
+    /// Add the binding to a packet parser
+    pub(crate) fn bind(&self, pb: &mut PacketParser) -> Result<(), PacketError> {
+        let next = layer_parser(&self.next)
+            .ok_or_else(|| PacketError::Config(format!("unknown layer: {}", self.next)))?;
+        let value = self.value;
+
+        match self.layer.as_str() {
+            "ether" => {
+                let ether_type = read_value::<EtherType>(&value.to_be_bytes())?;
+                pb.bind_layer(move |ether: &Ether, _rest| {
+                    if ether.ether_type == ether_type {
+                        Some(next)
+                    } else {
+                        None
+                    }
+                });
+            }
+            "ipv4" => {
+                let protocol = read_ip_protocol(value)?;
+                pb.bind_layer(move |ipv4: &Ipv4, _rest| {
+                    if ipv4.protocol == protocol {
+                        Some(next)
+                    } else {
+                        None
+                    }
+                });
+            }
+            "ipv6" => {
+                let protocol = read_ip_protocol(value)?;
+                pb.bind_layer(move |ipv6: &Ipv6, _rest| {
+                    if ipv6.upper_protocol() == protocol {
+                        Some(next)
+                    } else {
+                        None
+                    }
+                });
+            }
+            "gre" => {
+                let protocol = read_value::<EtherType>(&value.to_be_bytes())?;
+                pb.bind_layer(move |gre: &Gre, _rest| {
+                    if gre.protocol == protocol {
+                        Some(next)
+                    } else {
+                        None
+                    }
+                });
+            }
+            "tcp" => {
+                pb.bind_layer(move |tcp: &Tcp, _rest| {
+                    if tcp.sport == value || tcp.dport == value {
+                        Some(next)
+                    } else {
+                        None
+                    }
+                });
+            }
+            "udp" => {
+                pb.bind_layer(move |udp: &Udp, _rest| {
+                    if udp.sport == value || udp.dport == value {
+                        Some(next)
+                    } else {
+                        None
+                    }
+                });
+            }
+            _ => {
+                return Err(PacketError::Config(format!(
+                    "unsupported binding layer: {}",
+                    self.layer
+                )))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Lookup a layer parser by name
+fn layer_parser(name: &str) -> Option<LayerParser> {
+    let parser: LayerParser = match name {
+        "ether" => Ether::parse_layer,
+        "ipv4" => Ipv4::parse_layer,
+        "ipv6" => Ipv6::parse_layer,
+        "tcp" => Tcp::parse_layer,
+        "udp" => Udp::parse_layer,
+        "icmp4" => Icmp4::parse_layer,
+        "icmp6" => Icmp6::parse_layer,
+        "cdp" => Cdp::parse_layer,
+        "dns" => Dns::parse_layer,
+        "dns_tcp" => DnsTcp::parse_layer,
+        "vxlan" => Vxlan::parse_layer,
+        "gre" => Gre::parse_layer,
+        "raw" => Raw::parse_layer,
+        _ => return None,
+    };
+
+    Some(parser)
+}
+
+fn read_ip_protocol(value: u16) -> Result<IpProtocol, PacketError> {
+    let value = u8::try_from(value)
+        .map_err(|_e| PacketError::Config(format!("invalid ip protocol: {}", value)))?;
+    read_value::<IpProtocol>(&[value])
+}
+
+fn read_value<'a, T: DekuContainerRead<'a>>(input: &'a [u8]) -> Result<T, PacketError> {
+    T::from_bytes((input, 0))
+        .map(|(_rest, value)| value)
+        .map_err(|e| PacketError::Config(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_layer;
+    use alloc::vec;
+    use hexlit::hex;
+
+    // Ether / IP / UDP / DNS
+    const TEST_DATA: [u8; 71] = hex!("ffffffffffff000000000000080045000039000100004011a8f1c0a8010a08080808cf08003500257d02123401000001000000000000076578616d706c6503636f6d0000010001");
+
+    #[test]
+    fn test_parser_from_config() {
+        let config = ParserConfig {
+            bindings: vec![
+                BindingConfig::new("ether", 0x0800, "ipv4"),
+                BindingConfig::new("ipv4", 17, "udp"),
+                BindingConfig::new("udp", 53, "dns"),
+            ],
+        };
+        let pb = PacketParser::from_config(config).unwrap();
+
+        let (rest, packet) = pb.parse_packet::<Ether>(&TEST_DATA).unwrap();
+        assert!(rest.is_empty());
+
+        let layers = packet.layers();
+        assert_eq!(4, layers.len());
+        assert!(is_layer!(layers[0], Ether));
+        assert!(is_layer!(layers[1], Ipv4));
+        assert!(is_layer!(layers[2], Udp));
+        assert!(is_layer!(layers[3], Dns));
+    }
+
+    #[test]
+    fn test_parser_from_config_no_match() {
+        let config = ParserConfig {
+            bindings: vec![
+                BindingConfig::new("ether", 0x0800, "ipv4"),
+                BindingConfig::new("ipv4", 6, "tcp"),
+            ],
+        };
+        let pb = PacketParser::from_config(config).unwrap();
+
+        let (rest, packet) = pb.parse_packet::<Ether>(&TEST_DATA).unwrap();
+        assert_eq!(37, rest.len());
+        assert_eq!(2, packet.layers().len());
+    }
+
+    #[test]
+    fn test_parser_from_config_invalid() {
+        let config = ParserConfig {
+            bindings: vec![BindingConfig::new("ether", 0x0800, "unknown")],
+        };
+        assert_eq!(
+            Some(PacketError::Config("unknown layer: unknown".to_string())),
+            PacketParser::from_config(config).err()
+        );
+
+        let config = ParserConfig {
+            bindings: vec![BindingConfig::new("raw", 0, "ether")],
+        };
+        assert_eq!(
+            Some(PacketError::Config(
+                "unsupported binding layer: raw".to_string()
+            )),
+            PacketParser::from_config(config).err()
+        );
+
+        let config = ParserConfig {
+            bindings: vec![BindingConfig::new("ipv4", 256, "tcp")],
+        };
+        assert_eq!(
+            Some(PacketError::Config("invalid ip protocol: 256".to_string())),
+            PacketParser::from_config(config).err()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_parser_config_deserialize() {
+        let config: ParserConfig = serde_json::from_str(
+            r#"{
+                "bindings": [
+                    { "layer": "ether", "value": 2048, "next": "ipv4" },
+                    { "layer": "ipv4", "value": 17, "next": "udp" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            ParserConfig {
+                bindings: vec![
+                    BindingConfig::new("ether", 0x0800, "ipv4"),
+                    BindingConfig::new("ipv4", 17, "udp"),
+                ],
+            },
+            config
+        );
+    }
+}
@@ -2,6 +2,7 @@
   Packet error
 */
 use crate::layer::LayerError;
+use alloc::string::String;
 
 /// Error parsing or generating a packet
 #[derive(Debug, PartialEq)]
@@ -11,6 +12,27 @@ pub enum PacketError {
     Incomplete(usize),
     /// Error parsing a layer
     LayerError(LayerError),
+    /// Invalid packet parser configuration
+    Config(String),
+    /// Invalid hex string
+    InvalidHex(String),
+    /// Error fragmenting a packet
+    Fragment(String),
+}
+
+impl PacketError {
+    /// Static string describing the kind of error, without allocating
+    ///
+    /// Layer errors return the kind of the underlying [LayerError]
+    pub fn kind_str(&self) -> &'static str {
+        match self {
+            PacketError::Incomplete(_) => "incomplete",
+            PacketError::LayerError(e) => e.kind_str(),
+            PacketError::Config(_) => "config",
+            PacketError::InvalidHex(_) => "hex",
+            PacketError::Fragment(_) => "fragment",
+        }
+    }
 }
 
 impl From<LayerError> for PacketError {
@@ -38,4 +60,25 @@ mod tests {
         let packet_error = PacketError::from(layer_error.clone());
         assert_eq!(PacketError::LayerError(layer_error), packet_error);
     }
+
+    #[test]
+    fn test_kind_str() {
+        assert_eq!("incomplete", PacketError::Incomplete(8).kind_str());
+        assert_eq!(
+            "parse",
+            PacketError::LayerError(LayerError::Parse("some error".to_string())).kind_str()
+        );
+        assert_eq!(
+            "config",
+            PacketError::Config("some error".to_string()).kind_str()
+        );
+        assert_eq!(
+            "hex",
+            PacketError::InvalidHex("some error".to_string()).kind_str()
+        );
+        assert_eq!(
+            "fragment",
+            PacketError::Fragment("some error".to_string()).kind_str()
+        );
+    }
 }
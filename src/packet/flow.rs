@@ -0,0 +1,175 @@
+/*!
+Flow identification
+
+A [FlowKey] identifies the flow a packet belongs to, see [Packet::flow_key].
+*/
+use crate::{
+    get_layer,
+    layer::{
+        gre::Gre,
+        ip::{IpProtocol, Ipv4, Ipv6},
+        tcp::Tcp,
+        udp::Udp,
+        LayerOwned,
+    },
+    packet::Packet,
+};
+
+/// Ip address of a [FlowKey]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlowAddress {
+    /// Ipv4 address
+    V4(u32),
+    /// Ipv6 address
+    V6(u128),
+}
+
+/**
+Key identifying the flow of a packet
+
+The key is made of the innermost ip layer and the ports of the transport layer following it.
+When the ip layer is tunneled in a [Gre] layer carrying a key, the GRE key is part of the
+flow key, distinguishing flows multiplexed in the same tunnel.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlowKey {
+    /// Source address
+    pub src: FlowAddress,
+    /// Destination address
+    pub dst: FlowAddress,
+    /// Protocol of the ip payload
+    pub protocol: IpProtocol,
+    /// Source port, zero if the transport layer has no ports
+    pub sport: u16,
+    /// Destination port, zero if the transport layer has no ports
+    pub dport: u16,
+    /// Key of the [Gre] layer encapsulating the ip layer, if any
+    pub gre_key: Option<u32>,
+}
+
+fn ip_addresses(layer: &LayerOwned) -> Option<(FlowAddress, FlowAddress, IpProtocol)> {
+    if let Some(ipv4) = get_layer!(layer, Ipv4) {
+        Some((
+            FlowAddress::V4(ipv4.src),
+            FlowAddress::V4(ipv4.dst),
+            ipv4.protocol,
+        ))
+    } else {
+        get_layer!(layer, Ipv6).map(|ipv6| {
+            (
+                FlowAddress::V6(ipv6.src),
+                FlowAddress::V6(ipv6.dst),
+                ipv6.upper_protocol(),
+            )
+        })
+    }
+}
+
+impl Packet {
+    /// Flow key of the packet, `None` if the packet has no ip layer
+    ///
+    /// See [FlowKey]
+    pub fn flow_key(&self) -> Option<FlowKey> {
+        let layers = self.layers();
+        let (index, (src, dst, protocol)) = layers
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, layer)| ip_addresses(layer).map(|addresses| (i, addresses)))?;
+
+        let (sport, dport) = match layers.get(index + 1) {
+            Some(layer) => {
+                if let Some(tcp) = get_layer!(layer, Tcp) {
+                    (tcp.sport, tcp.dport)
+                } else if let Some(udp) = get_layer!(layer, Udp) {
+                    (udp.sport, udp.dport)
+                } else {
+                    (0, 0)
+                }
+            }
+            None => (0, 0),
+        };
+
+        let gre_key = index
+            .checked_sub(1)
+            .and_then(|i| get_layer!(layers[i], Gre))
+            .and_then(|gre| gre.key);
+
+        Some(FlowKey {
+            src,
+            dst,
+            protocol,
+            sport,
+            dport,
+            gre_key,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::ether::Ether;
+    use alloc::{boxed::Box, format, vec};
+
+    fn gre_packet(key: &str) -> Packet {
+        // Ether / IP(proto=GRE) / GRE(key) / IP / UDP(1234, 5678)
+        Packet::from_hex(&format!(
+            "feff20000100000001000000 0800
+            4500003800010000402f0000 0a000001 0a000002
+            2000 0800 {}
+            4500001c0001000040110000 c0a80001 c0a80002
+            04d2 162e 0008 0000",
+            key
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_flow_key_gre() {
+        let packet_a = gre_packet("00000001");
+        let packet_b = gre_packet("00000002");
+
+        let key_a = packet_a.flow_key().unwrap();
+        let key_b = packet_b.flow_key().unwrap();
+
+        assert_eq!(
+            FlowKey {
+                src: FlowAddress::V4(0xc0a80001),
+                dst: FlowAddress::V4(0xc0a80002),
+                protocol: IpProtocol::UDP,
+                sport: 1234,
+                dport: 5678,
+                gre_key: Some(1),
+            },
+            key_a
+        );
+        assert_eq!(Some(2), key_b.gre_key);
+        assert_ne!(key_a, key_b);
+        assert_eq!(key_a, gre_packet("00000001").flow_key().unwrap());
+    }
+
+    #[test]
+    fn test_flow_key_gre_ipv6() {
+        // Ether / IPv6(nh=GRE) / GRE(key) / IP / UDP(1234, 5678)
+        let packet = Packet::from_hex(
+            "feff20000100000001000000 86dd
+            6000000000242f40 20010db8000000000000000000000001 20010db8000000000000000000000002
+            2000 0800 00000007
+            4500001c0001000040110000 c0a80001 c0a80002
+            04d2 162e 0008 0000",
+        )
+        .unwrap();
+
+        let key = packet.flow_key().unwrap();
+        assert_eq!(FlowAddress::V4(0xc0a80001), key.src);
+        assert_eq!(IpProtocol::UDP, key.protocol);
+        assert_eq!(Some(7), key.gre_key);
+    }
+
+    #[test]
+    fn test_flow_key_none() {
+        let packet = Packet::from_layers(vec![Box::new(Ether::default())]);
+        assert_eq!(None, packet.flow_key());
+    }
+}
@@ -0,0 +1,366 @@
+/*!
+Ipv4 fragmentation
+
+An [Ipv4FragmentBuilder] splits a packet into ipv4 fragments, each fragment being a full
+packet made of the layers up to and including the [Ipv4] layer of the template, followed
+by a [Raw] layer holding a slice of the ipv4 payload.
+*/
+use crate::{
+    get_layer,
+    layer::{
+        ip::{
+            ipv4::{Ipv4Option, Ipv4OptionClass, Ipv4OptionType},
+            Ipv4,
+        },
+        raw::Raw,
+        utils::layers_to_bytes,
+        LayerExt, LayerOwned,
+    },
+    packet::{Packet, PacketError},
+};
+use alloc::{boxed::Box, format, string::ToString, vec::Vec};
+use core::convert::TryFrom;
+
+/// More Fragments flag of the ipv4 flags field
+const MORE_FRAGMENTS: u8 = 0b001;
+
+/// Don't Fragment flag of the ipv4 flags field
+const DONT_FRAGMENT: u8 = 0b010;
+
+/// Maximum value of the 13 bit ipv4 fragment offset
+const MAX_FRAGMENT_OFFSET: usize = 0x1fff;
+
+/**
+Builder of ipv4 fragments
+
+The packet is finalized before being split, such that checksums of upper layers, computed
+over the whole payload, are correct once reassembled. Each fragment is finalized to update
+the ipv4 length and checksum.
+
+The first fragment keeps all ipv4 options of the template, the following fragments only
+keep the options with the copied flag set, as specified by RFC 791. Option bytes which could
+not be parsed (see [Ipv4::parse_lenient]) are only kept in the first fragment.
+
+A packet with the Don't Fragment flag set which does not fit in a single fragment is an
+error.
+
+# Example
+
+```rust
+# use hatchet::{
+#   layer::{ether::Ether, ip::{IpProtocol, Ipv4}, raw::Raw, udp::Udp},
+#   packet::{Ipv4FragmentBuilder, Packet},
+# };
+let packet = Packet::from_layers(vec![
+    Box::new(Ether::default()),
+    Box::new(Ipv4 { protocol: IpProtocol::UDP, ..Default::default() }),
+    Box::new(Udp::default()),
+    Box::new(Raw { data: vec![0xAA; 40], bit_offset: 0 }),
+]);
+
+let fragments = Ipv4FragmentBuilder::new(16).build(&packet).unwrap();
+assert_eq!(3, fragments.len());
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ipv4FragmentBuilder {
+    fragment_size: usize,
+}
+
+impl Ipv4FragmentBuilder {
+    /// Create a builder producing fragments carrying `fragment_size` bytes of ipv4 payload
+    ///
+    /// The fragment size must be a non-zero multiple of 8, the last fragment may be smaller.
+    pub fn new(fragment_size: usize) -> Self {
+        Ipv4FragmentBuilder { fragment_size }
+    }
+
+    /// Split `packet` into fragments at its first [Ipv4] layer
+    pub fn build(&self, packet: &Packet) -> Result<Vec<Packet>, PacketError> {
+        // fragment offsets are in units of 8 bytes
+        if self.fragment_size == 0 || self.fragment_size & 0b111 != 0 {
+            return Err(PacketError::Fragment(format!(
+                "fragment size {} is not a non-zero multiple of 8",
+                self.fragment_size
+            )));
+        }
+
+        let mut packet = packet.clone();
+        packet.finalize()?;
+
+        let layers = packet.layers();
+        let index = layers
+            .iter()
+            .position(|layer| get_layer!(layer, Ipv4).is_some())
+            .ok_or_else(|| PacketError::Fragment("packet has no ipv4 layer".to_string()))?;
+        let ipv4 = get_layer!(layers[index], Ipv4).expect("position of an ipv4 layer");
+
+        let template = &layers[..index];
+        let payload = layers_to_bytes(&layers[index + 1..])?;
+
+        // Fragmenting a fragment keeps its offset and More Fragments flag on the last fragment
+        let base_offset = usize::from(ipv4.offset) * 8;
+        let more_fragments = ipv4.flags & MORE_FRAGMENTS;
+
+        let chunks: Vec<&[u8]> = payload.chunks(self.fragment_size).collect();
+        if chunks.len() > 1 && ipv4.flags & DONT_FRAGMENT != 0 {
+            return Err(PacketError::Fragment(
+                "packet has the don't fragment flag set".to_string(),
+            ));
+        }
+
+        let copied_header = copied_options_header(ipv4)?;
+        let mut fragments = Vec::with_capacity(chunks.len());
+        for (i, chunk) in chunks.iter().enumerate() {
+            let offset = (base_offset + i * self.fragment_size) / 8;
+            if offset > MAX_FRAGMENT_OFFSET {
+                return Err(PacketError::Fragment(format!(
+                    "fragment offset {} exceeds {}",
+                    offset, MAX_FRAGMENT_OFFSET
+                )));
+            }
+
+            let flags = if i + 1 < chunks.len() {
+                ipv4.flags | MORE_FRAGMENTS
+            } else {
+                (ipv4.flags & !MORE_FRAGMENTS) | more_fragments
+            };
+
+            let header = if i == 0 { ipv4 } else { &copied_header };
+
+            let mut fragment_layers: Vec<LayerOwned> = template.to_vec();
+            fragment_layers.push(Box::new(Ipv4 {
+                flags,
+                offset: u16::try_from(offset).expect("offset bounded by MAX_FRAGMENT_OFFSET"),
+                ..header.clone()
+            }));
+            fragment_layers.push(Box::new(Raw {
+                data: chunk.to_vec(),
+                bit_offset: 0,
+            }));
+
+            let mut fragment = Packet::from_layers(fragment_layers);
+            fragment.finalize_layer(index)?;
+
+            fragments.push(fragment);
+        }
+
+        Ok(fragments)
+    }
+}
+
+/// Ipv4 header of the fragments following the first one
+///
+/// Only the options with the copied flag set are kept, padded with End of Option List to a
+/// multiple of 4 bytes.
+fn copied_options_header(ipv4: &Ipv4) -> Result<Ipv4, PacketError> {
+    let mut header = Ipv4 {
+        options: ipv4
+            .options
+            .iter()
+            .filter(|option| option.copied != 0)
+            .cloned()
+            .collect(),
+        options_raw: Vec::new(),
+        ..ipv4.clone()
+    };
+
+    let mut length = header.length()?;
+    while length % 4 != 0 {
+        header.options.push(Ipv4Option {
+            copied: 0,
+            class: Ipv4OptionClass::Control,
+            option: Ipv4OptionType::EOOL,
+        });
+        length += 1;
+    }
+    header.ihl = match u8::try_from(length / 4) {
+        Ok(ihl) if ihl <= 0xf => ihl,
+        _ => {
+            return Err(PacketError::Fragment(format!(
+                "ipv4 header length {} exceeds 60",
+                length
+            )))
+        }
+    };
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::{
+        ether::Ether,
+        ip::{checksum, IpProtocol},
+        udp::Udp,
+    };
+    use alloc::vec;
+
+    fn template() -> Packet {
+        Packet::from_layers(vec![
+            Box::new(Ether::default()),
+            Box::new(Ipv4 {
+                protocol: IpProtocol::UDP,
+                identification: 0x1234,
+                ..Default::default()
+            }),
+            Box::new(Udp {
+                sport: 1234,
+                dport: 5678,
+                ..Default::default()
+            }),
+            Box::new(Raw {
+                data: (0..40).collect(),
+                bit_offset: 0,
+            }),
+        ])
+    }
+
+    #[test]
+    fn test_ipv4_fragment_builder() {
+        let packet = template();
+        let fragments = Ipv4FragmentBuilder::new(16).build(&packet).unwrap();
+        assert_eq!(3, fragments.len());
+
+        let mut reassembled = Vec::new();
+        for (i, fragment) in fragments.iter().enumerate() {
+            let layers = fragment.layers();
+            assert_eq!(3, layers.len());
+            assert!(get_layer!(layers[0], Ether).is_some());
+
+            let ipv4 = get_layer!(layers[1], Ipv4).unwrap();
+            assert_eq!(0x1234, ipv4.identification);
+            assert_eq!(i as u16 * 2, ipv4.offset);
+            assert_eq!(
+                i < 2,
+                ipv4.flags & MORE_FRAGMENTS != 0,
+                "fragment {} more fragments flag",
+                i
+            );
+
+            // checksum over a header with a valid checksum is zero
+            assert_eq!(0, checksum(&ipv4.to_bytes().unwrap()));
+
+            let raw = get_layer!(layers[2], Raw).unwrap();
+            assert_eq!(usize::from(ipv4.length), 20 + raw.data.len());
+            reassembled.extend_from_slice(&raw.data);
+        }
+
+        let mut finalized = packet.clone();
+        finalized.finalize().unwrap();
+        assert_eq!(
+            layers_to_bytes(&finalized.layers()[2..]).unwrap(),
+            reassembled
+        );
+    }
+
+    #[test]
+    fn test_ipv4_fragment_builder_single() {
+        let fragments = Ipv4FragmentBuilder::new(64).build(&template()).unwrap();
+        assert_eq!(1, fragments.len());
+
+        let ipv4 = get_layer!(fragments[0].layers()[1], Ipv4).unwrap();
+        assert_eq!(0, ipv4.offset);
+        assert_eq!(0, ipv4.flags & MORE_FRAGMENTS);
+    }
+
+    #[test]
+    fn test_ipv4_fragment_builder_options() {
+        // loose source route, copied
+        let lsrr = Ipv4Option {
+            copied: 1,
+            class: Ipv4OptionClass::Control,
+            option: Ipv4OptionType::Unknown {
+                type_: 3,
+                length: 7,
+                value: vec![4, 0x0a, 0x00, 0x00, 0x01],
+            },
+        };
+        // timestamp, not copied
+        let timestamp = Ipv4Option {
+            copied: 0,
+            class: Ipv4OptionClass::Debug,
+            option: Ipv4OptionType::Unknown {
+                type_: 4,
+                length: 4,
+                value: vec![5, 0],
+            },
+        };
+        let nop = Ipv4Option {
+            copied: 0,
+            class: Ipv4OptionClass::Control,
+            option: Ipv4OptionType::NOP,
+        };
+        let eool = Ipv4Option {
+            copied: 0,
+            class: Ipv4OptionClass::Control,
+            option: Ipv4OptionType::EOOL,
+        };
+
+        let mut packet = template();
+        packet.layers_mut()[1] = Box::new(Ipv4 {
+            ihl: 8,
+            protocol: IpProtocol::UDP,
+            options: vec![nop.clone(), lsrr.clone(), timestamp.clone()],
+            ..Default::default()
+        });
+
+        let fragments = Ipv4FragmentBuilder::new(16).build(&packet).unwrap();
+        assert_eq!(3, fragments.len());
+
+        let first = get_layer!(fragments[0].layers()[1], Ipv4).unwrap();
+        assert_eq!(8, first.ihl);
+        assert_eq!(vec![nop, lsrr.clone(), timestamp], first.options);
+
+        for fragment in &fragments[1..] {
+            let ipv4 = get_layer!(fragment.layers()[1], Ipv4).unwrap();
+            assert_eq!(7, ipv4.ihl);
+            assert_eq!(vec![lsrr.clone(), eool.clone()], ipv4.options);
+            assert!(ipv4.validate().is_ok());
+            assert_eq!(0, checksum(&ipv4.to_bytes().unwrap()));
+
+            let raw = get_layer!(fragment.layers()[2], Raw).unwrap();
+            assert_eq!(usize::from(ipv4.length), 28 + raw.data.len());
+        }
+    }
+
+    #[test]
+    fn test_ipv4_fragment_builder_dont_fragment() {
+        let mut packet = template();
+        packet.layers_mut()[1] = Box::new(Ipv4 {
+            flags: DONT_FRAGMENT,
+            protocol: IpProtocol::UDP,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            Some(PacketError::Fragment(
+                "packet has the don't fragment flag set".to_string()
+            )),
+            Ipv4FragmentBuilder::new(16).build(&packet).err()
+        );
+
+        // fits in a single fragment
+        let fragments = Ipv4FragmentBuilder::new(64).build(&packet).unwrap();
+        assert_eq!(1, fragments.len());
+    }
+
+    #[test]
+    fn test_ipv4_fragment_builder_errors() {
+        assert_eq!(
+            Some(PacketError::Fragment(
+                "fragment size 12 is not a non-zero multiple of 8".to_string()
+            )),
+            Ipv4FragmentBuilder::new(12).build(&template()).err()
+        );
+
+        let packet = Packet::from_layers(vec![Box::new(Ether::default())]);
+        assert_eq!(
+            Some(PacketError::Fragment(
+                "packet has no ipv4 layer".to_string()
+            )),
+            Ipv4FragmentBuilder::new(8).build(&packet).err()
+        );
+    }
+}
@@ -0,0 +1,223 @@
+/*!
+Link types
+
+A [LinkType] identifies the first layer of captured data, mirroring the pcap `DLT_` / `LINKTYPE_`
+values. See [PacketParser::parse_for_linktype](crate::packet::PacketParser::parse_for_linktype).
+*/
+use crate::{
+    layer::{
+        ether::Ether,
+        ip::{Ipv4, Ipv6},
+        raw::Raw,
+    },
+    packet::{Packet, PacketError, PacketParser},
+};
+
+/// Link layer type of captured data, see [tcpdump link types](https://www.tcpdump.org/linktypes.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LinkType {
+    /// Ethernet, starting at [Ether]
+    Ethernet,
+    /// Raw ip, [Ipv4] or [Ipv6] depending on the version of the packet
+    RawIp,
+    /// Raw ipv4, starting at [Ipv4]
+    RawIpv4,
+    /// Raw ipv6, starting at [Ipv6]
+    RawIpv6,
+    /// Linux cooked capture
+    Sll,
+    /// Radiotap header followed by an 802.11 header
+    Radiotap,
+    /// Any other link type
+    Other(u32),
+}
+
+impl LinkType {
+    /// Value of the link type, as found in pcap headers
+    pub fn value(&self) -> u32 {
+        match self {
+            LinkType::Ethernet => 1,
+            LinkType::RawIp => 101,
+            LinkType::Sll => 113,
+            LinkType::Radiotap => 127,
+            LinkType::RawIpv4 => 228,
+            LinkType::RawIpv6 => 229,
+            LinkType::Other(value) => *value,
+        }
+    }
+}
+
+impl LinkType {
+    /// Guess the link type of raw bytes from their first byte
+    ///
+    /// Data starting with a version nibble of 4 and a valid ipv4 header length is
+    /// [RawIpv4](LinkType::RawIpv4), data starting with a version nibble of 6 is
+    /// [RawIpv6](LinkType::RawIpv6), any other data is [Ethernet](LinkType::Ethernet).
+    ///
+    /// This is a heuristic, an ethernet frame with a destination mac address starting with
+    /// such a byte is detected as raw ip.
+    pub fn detect(input: &[u8]) -> LinkType {
+        match input.first() {
+            Some(b) if b >> 4 == 4 && b & 0x0f >= 5 => LinkType::RawIpv4,
+            Some(b) if b >> 4 == 6 => LinkType::RawIpv6,
+            _ => LinkType::Ethernet,
+        }
+    }
+}
+
+impl From<u32> for LinkType {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => LinkType::Ethernet,
+            101 => LinkType::RawIp,
+            113 => LinkType::Sll,
+            127 => LinkType::Radiotap,
+            228 => LinkType::RawIpv4,
+            229 => LinkType::RawIpv6,
+            value => LinkType::Other(value),
+        }
+    }
+}
+
+impl PacketParser {
+    /// Parse a packet starting at the first layer of the link type
+    ///
+    /// Link types without a corresponding layer in the crate are parsed as [Raw].
+    ///
+    /// ```rust
+    /// # use hatchet::packet::{LinkType, PacketParser};
+    /// # use hexlit::hex;
+    /// let data = hex!("feff20000100 000001000000 0800");
+    /// let (_rest, packet) = PacketParser::new()
+    ///     .parse_for_linktype(LinkType::Ethernet, &data)
+    ///     .unwrap();
+    /// ```
+    pub fn parse_for_linktype<'a>(
+        &self,
+        link: LinkType,
+        input: &'a [u8],
+    ) -> Result<(&'a [u8], Packet), PacketError> {
+        match link {
+            LinkType::Ethernet => self.parse_packet::<Ether>(input),
+            LinkType::RawIpv4 => self.parse_packet::<Ipv4>(input),
+            LinkType::RawIpv6 => self.parse_packet::<Ipv6>(input),
+            LinkType::RawIp => match input.first().map(|b| b >> 4) {
+                Some(4) => self.parse_packet::<Ipv4>(input),
+                Some(6) => self.parse_packet::<Ipv6>(input),
+                _ => self.parse_packet::<Raw>(input),
+            },
+            LinkType::Sll | LinkType::Radiotap | LinkType::Other(_) => {
+                self.parse_packet::<Raw>(input)
+            }
+        }
+    }
+
+    /// Parse a packet starting at a layer detected from the data
+    ///
+    /// See [LinkType::detect] for the detection of the first layer.
+    pub fn parse_autodetect<'a>(&self, input: &'a [u8]) -> Result<(&'a [u8], Packet), PacketError> {
+        self.parse_for_linktype(LinkType::detect(input), input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_layer, layer::udp::Udp};
+    use hexlit::hex;
+    use rstest::*;
+
+    const IPV4_UDP: [u8; 28] = hex!(
+        "4500001c0001000040110000 c0a80001 c0a80002
+        04d2162e00080000"
+    );
+
+    const IPV6_UDP: [u8; 48] = hex!(
+        "6000000000081140 00000000000000000000000000000001 00000000000000000000000000000002
+        04d2162e00080000"
+    );
+
+    #[rstest(
+        link,
+        value,
+        case(LinkType::Ethernet, 1),
+        case(LinkType::RawIp, 101),
+        case(LinkType::RawIpv4, 228),
+        case(LinkType::RawIpv6, 229),
+        case(LinkType::Sll, 113),
+        case(LinkType::Radiotap, 127),
+        case(LinkType::Other(147), 147)
+    )]
+    fn test_linktype_value(link: LinkType, value: u32) {
+        assert_eq!(value, link.value());
+        assert_eq!(link, LinkType::from(value));
+    }
+
+    #[rstest(input, expected,
+        case(&IPV4_UDP, LinkType::RawIpv4),
+        case(&IPV6_UDP, LinkType::RawIpv6),
+        case(&hex!("feff20000100 000001000000 0800"), LinkType::Ethernet),
+        case(&hex!("40ff20000100 000001000000 0800"), LinkType::Ethernet),
+        case(&[], LinkType::Ethernet),
+    )]
+    fn test_linktype_detect(input: &[u8], expected: LinkType) {
+        assert_eq!(expected, LinkType::detect(input));
+    }
+
+    #[test]
+    fn test_parse_autodetect() {
+        let parser = PacketParser::new();
+
+        let (_rest, packet) = parser.parse_autodetect(&IPV4_UDP).unwrap();
+        let layers = packet.layers();
+        assert_eq!(2, layers.len());
+        assert!(get_layer!(layers[0], Ipv4).is_some());
+        assert!(get_layer!(layers[1], Udp).is_some());
+
+        let mut ether = hex!("feff20000100 000001000000 0800").to_vec();
+        ether.extend_from_slice(&IPV4_UDP);
+        let (_rest, packet) = parser.parse_autodetect(&ether).unwrap();
+        let layers = packet.layers();
+        assert_eq!(3, layers.len());
+        assert!(get_layer!(layers[0], Ether).is_some());
+        assert!(get_layer!(layers[1], Ipv4).is_some());
+        assert!(get_layer!(layers[2], Udp).is_some());
+    }
+
+    #[test]
+    fn test_parse_for_linktype() {
+        let parser = PacketParser::new();
+
+        let mut ether = hex!("feff20000100 000001000000 0800").to_vec();
+        ether.extend_from_slice(&IPV4_UDP);
+        let (_rest, packet) = parser
+            .parse_for_linktype(LinkType::Ethernet, &ether)
+            .unwrap();
+        let layers = packet.layers();
+        assert_eq!(3, layers.len());
+        assert!(get_layer!(layers[0], Ether).is_some());
+        assert!(get_layer!(layers[1], Ipv4).is_some());
+        assert!(get_layer!(layers[2], Udp).is_some());
+
+        for link in [LinkType::RawIpv4, LinkType::RawIp] {
+            let (_rest, raw_ip) = parser.parse_for_linktype(link, &IPV4_UDP).unwrap();
+            let raw_layers = raw_ip.layers();
+            assert_eq!(2, raw_layers.len(), "{:?}", link);
+            assert_eq!(get_layer!(layers[1], Ipv4), get_layer!(raw_layers[0], Ipv4));
+            assert_eq!(get_layer!(layers[2], Udp), get_layer!(raw_layers[1], Udp));
+        }
+
+        for link in [LinkType::RawIpv6, LinkType::RawIp] {
+            let (_rest, packet) = parser.parse_for_linktype(link, &IPV6_UDP).unwrap();
+            let layers = packet.layers();
+            assert_eq!(2, layers.len(), "{:?}", link);
+            assert!(get_layer!(layers[0], Ipv6).is_some());
+            assert!(get_layer!(layers[1], Udp).is_some());
+        }
+
+        let (_rest, packet) = parser.parse_for_linktype(LinkType::Sll, &ether).unwrap();
+        assert_eq!(1, packet.layers().len());
+        assert!(get_layer!(packet.layers()[0], Raw).is_some());
+    }
+}
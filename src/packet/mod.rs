@@ -3,18 +3,51 @@ Packet parsing and construction.
 */
 
 use crate::{
-    get_layer,
-    layer::{LayerExt, LayerOwned, LayerRef},
+    get_layer, is_layer,
+    layer::{
+        dns::{Dns, DnsTcp},
+        ether::{Ether, FCS_LENGTH, INTERFRAME_GAP_LENGTH, MIN_FRAME_LENGTH, PREAMBLE_SFD},
+        ip::{
+            ipv6::{Ipv6Extension, Ipv6RoutingData},
+            Ipv4, Ipv6,
+        },
+        raw::Raw,
+        tcp::Tcp,
+        udp::Udp,
+        LayerError, LayerExt, LayerOwned, LayerRef,
+    },
+};
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
 };
-use alloc::{boxed::Box, vec, vec::Vec};
 use core::any::TypeId;
+use core::time::Duration;
 use hashbrown::HashMap;
 
+pub mod anonymize;
+pub use anonymize::AnonScheme;
+
 pub mod bindings;
 
+pub mod config;
+pub use config::ParserConfig;
+
 pub mod error;
 pub use error::PacketError;
 
+pub mod flow;
+pub use flow::{FlowAddress, FlowKey};
+
+pub mod fragment;
+pub use fragment::Ipv4FragmentBuilder;
+
+pub mod linktype;
+pub use linktype::LinkType;
+
 /// Read-only view of a packet
 pub struct PacketView<'a> {
     #[allow(dead_code)]
@@ -32,6 +65,7 @@ impl<'a> PacketView<'a> {
 #[derive(Debug, Clone)]
 pub struct Packet {
     layers: Vec<LayerOwned>,
+    timestamp: Option<Duration>,
 }
 
 impl Packet {
@@ -42,19 +76,207 @@ impl Packet {
 
     /// Construct a Packet given existing layers
     pub fn from_layers(layers: Vec<LayerOwned>) -> Self {
-        Self { layers }
+        Self {
+            layers,
+            timestamp: None,
+        }
+    }
+
+    /// Create a finalized packet ending in a default `T`, with the lower layers it requires
+    ///
+    /// The lower layers follow the default [bindings](self::bindings), down to [Ether]. For
+    /// example [Tcp] is carried by [Ipv4] over [Ether], [Dns] by [Udp] with destination port
+    /// 53. Layers without a default binding are returned on their own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` fails to [finalize](Self::finalize) in its default state, the lower
+    /// layers always finalize.
+    ///
+    /// ```rust
+    /// # use hatchet::{is_layer, packet::Packet, layer::{ether::Ether, ip::Ipv4, tcp::Tcp}};
+    /// let packet = Packet::template_for::<Tcp>();
+    /// assert_eq!(3, packet.layers().len());
+    /// assert!(is_layer!(packet.layers()[0], Ether));
+    /// assert!(is_layer!(packet.layers()[1], Ipv4));
+    /// assert!(is_layer!(packet.layers()[2], Tcp));
+    /// ```
+    pub fn template_for<T: LayerExt + Default + 'static>() -> Packet {
+        let mut layers = bindings::lower_layers(TypeId::of::<T>());
+
+        // the default ipv6 layer has no version set
+        if TypeId::of::<T>() == TypeId::of::<Ipv6>() {
+            layers.push(Box::new(Ipv6 {
+                version: 6,
+                ..Default::default()
+            }));
+        } else {
+            layers.push(Box::new(T::default()));
+        }
+
+        let mut packet = Packet::from_layers(layers);
+        packet
+            .finalize()
+            .expect("finalizing a template of default layers should not fail");
+
+        packet
+    }
+
+    /// Parse the trailing [Raw] layer into one more layer with the default [PacketParser]
+    ///
+    /// With the `std` feature the default parser is built once per thread, otherwise it is
+    /// built on every call, in which case [descend_with](Self::descend_with) with a reused
+    /// parser is cheaper.
+    pub fn descend(&mut self) -> Result<bool, PacketError> {
+        #[cfg(feature = "std")]
+        {
+            std::thread_local! {
+                static DEFAULT_PARSER: PacketParser = PacketParser::new();
+            }
+
+            DEFAULT_PARSER.with(|parser| self.descend_with(parser))
+        }
+
+        #[cfg(not(feature = "std"))]
+        {
+            self.descend_with(&PacketParser::new())
+        }
+    }
+
+    /// Parse the trailing [Raw] layer into one more layer using the bindings of `parser`
+    ///
+    /// This allows parsing deeper, such as into tunneled packets, on demand. The layer is
+    /// selected by the bindings of the layer preceding the [Raw] layer, un-parsed data
+    /// remains in a trailing [Raw] layer.
+    ///
+    /// Returns whether a layer was parsed, the packet is left unchanged otherwise.
+    ///
+    /// The same `parser` should be reused across packets, building a [PacketParser] creates
+    /// all of its bindings.
+    pub fn descend_with(&mut self, parser: &PacketParser) -> Result<bool, PacketError> {
+        let (prev, raw) = match self.layers.as_slice() {
+            [.., prev, last] => match get_layer!(last, Raw) {
+                Some(raw) if raw.bit_offset == 0 => (prev, raw),
+                _ => return Ok(false),
+            },
+            _ => return Ok(false),
+        };
+
+        let next_layer_parser = match parser.next_layer_parser(prev.as_ref(), &raw.data) {
+            Some(next_layer_parser) => next_layer_parser,
+            None => return Ok(false),
+        };
+
+        let (rest, layer) = next_layer_parser(&raw.data)?;
+        if is_layer!(layer, Raw) {
+            return Ok(false);
+        }
+
+        let rest = rest.to_vec();
+        if let Some(last) = self.layers.last_mut() {
+            *last = layer;
+        }
+        if !rest.is_empty() {
+            self.layers.push(Box::new(Raw {
+                data: rest,
+                bit_offset: 0,
+            }));
+        }
+
+        Ok(true)
+    }
+
+    /// Parse a packet from a hex string, starting at [Ether] with the default [PacketParser]
+    ///
+    /// Whitespace in the string is ignored
+    ///
+    /// ```rust
+    /// # use hatchet::packet::Packet;
+    /// let packet = Packet::from_hex("feff20000100 000001000000 0800").unwrap();
+    /// assert_eq!(1, packet.layers().len());
+    /// ```
+    pub fn from_hex(s: &str) -> Result<Packet, PacketError> {
+        let digits: Vec<char> = s.chars().filter(|c| !c.is_whitespace()).collect();
+
+        let pairs = digits.chunks_exact(2);
+        if !pairs.remainder().is_empty() {
+            return Err(PacketError::InvalidHex(
+                "odd number of hex digits".to_string(),
+            ));
+        }
+
+        let data = pairs
+            .map(|pair| match (pair[0].to_digit(16), pair[1].to_digit(16)) {
+                (Some(high), Some(low)) => Ok((high << 4 | low) as u8),
+                _ => Err(PacketError::InvalidHex(format!(
+                    "invalid hex digits: {}{}",
+                    pair[0], pair[1]
+                ))),
+            })
+            .collect::<Result<Vec<u8>, PacketError>>()?;
+
+        let (_rest, packet) = PacketParser::new().parse_packet::<Ether>(&data)?;
+        Ok(packet)
+    }
+
+    /// Capture timestamp of the packet, as a duration since the unix epoch
+    ///
+    /// This is set by interfaces providing a timestamp when reading, such as
+    /// [PcapFile](crate::datalink::pcapfile::PcapFile)
+    pub fn timestamp(&self) -> Option<Duration> {
+        self.timestamp
+    }
+
+    /// Set the capture timestamp of the packet, as a duration since the unix epoch
+    pub fn set_timestamp(&mut self, timestamp: Duration) {
+        self.timestamp = Some(timestamp);
+    }
+
+    /// Remove the capture metadata of the packet, such as the timestamp
+    ///
+    /// Useful when crafting a new packet from a captured one
+    pub fn clear_metadata(&mut self) {
+        self.timestamp = None;
     }
 
     /// Finalize a packet
     ///
-    /// This will call finalize on each layer of the packet
+    /// This will call finalize on each layer of the packet, starting from the innermost layer
+    /// such that lengths and checksums of a layer cover its finalized payload
     pub fn finalize(&mut self) -> Result<(), PacketError> {
-        for i in 0..self.layers.len() {
-            let (prev, rest) = self.layers.split_at_mut(i);
-            let (current, next) = rest.split_at_mut(1);
+        for i in (0..self.layers.len()).rev() {
+            self.finalize_layer(i)?;
+        }
 
-            let layer = current.first_mut().expect("dev error: should never panic");
-            layer.finalize(prev, next)?;
+        Ok(())
+    }
+
+    /// Finalize a single layer of the packet
+    ///
+    /// Useful to update a layer, such as recomputing a checksum, after a targeted edit
+    /// without finalizing the other layers
+    pub fn finalize_layer(&mut self, index: usize) -> Result<(), PacketError> {
+        if index >= self.layers.len() {
+            return Err(PacketError::LayerError(LayerError::Finalize(format!(
+                "layer index {} out of range for packet of {} layers",
+                index,
+                self.layers.len()
+            ))));
+        }
+
+        let (prev, rest) = self.layers.split_at_mut(index);
+        let (current, next) = rest.split_at_mut(1);
+
+        let layer = current.first_mut().expect("dev error: should never panic");
+        layer.finalize(prev, next)?;
+
+        Ok(())
+    }
+
+    /// Validate each layer of the packet, see [LayerExt::validate]
+    pub fn validate(&self) -> Result<(), PacketError> {
+        for layer in self.layers.iter() {
+            layer.validate()?;
         }
 
         Ok(())
@@ -70,18 +292,276 @@ impl Packet {
         &mut self.layers
     }
 
+    /// Transform each layer of the packet, returning the new packet
+    ///
+    /// Layers can be substituted, such as replacing a [Raw] layer with a parsed layer.
+    /// The capture metadata of the packet is kept.
+    pub fn map_layers<F: FnMut(LayerOwned) -> LayerOwned>(self, f: F) -> Packet {
+        Packet {
+            layers: self.layers.into_iter().map(f).collect(),
+            timestamp: self.timestamp,
+        }
+    }
+
+    /// Remove and return the first layer of the packet
+    ///
+    /// Useful to decapsulate a packet, such as stripping the link layer
+    pub fn pop_front(&mut self) -> Option<LayerOwned> {
+        if self.layers.is_empty() {
+            None
+        } else {
+            Some(self.layers.remove(0))
+        }
+    }
+
+    /// Remove and return the last layer of the packet
+    pub fn pop_back(&mut self) -> Option<LayerOwned> {
+        self.layers.pop()
+    }
+
     /// Packet to bytes
     pub fn to_bytes(&self) -> Result<Vec<u8>, PacketError> {
         Ok(crate::layer::utils::layers_to_bytes(&self.layers)?)
     }
+
+    /// Serialize a finalized copy of the packet, leaving the packet untouched
+    pub fn to_bytes_finalized(&self) -> Result<Vec<u8>, PacketError> {
+        let mut packet = self.clone();
+        packet.finalize()?;
+        packet.to_bytes()
+    }
+
+    /// Returns true if both packets serialize to the same bytes once finalized
+    ///
+    /// Stale fields updated on finalize, such as checksums and lengths, are ignored.
+    /// Returns false if either packet fails to finalize or serialize.
+    pub fn normalized_eq(&self, other: &Packet) -> bool {
+        match (self.to_bytes_finalized(), other.to_bytes_finalized()) {
+            (Ok(bytes), Ok(other_bytes)) => bytes == other_bytes,
+            _ => false,
+        }
+    }
+
+    /// Serialized transport payload of the packet
+    ///
+    /// Returns the bytes of all the layers following the innermost [Tcp] or [Udp] layer,
+    /// regardless of whether they were parsed as [Raw](crate::layer::raw::Raw) or as an
+    /// application layer. Returns `None` if the packet has no transport layer.
+    pub fn transport_payload(&self) -> Result<Option<Vec<u8>>, PacketError> {
+        let index = self
+            .layers
+            .iter()
+            .rposition(|layer| is_layer!(layer, Tcp) || is_layer!(layer, Udp));
+
+        match index {
+            Some(index) => Ok(Some(crate::layer::utils::layers_to_bytes(
+                &self.layers[index + 1..],
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Anonymize the mac and ip addresses of the packet
+    ///
+    /// Addresses of [Ether], [Ipv4] and [Ipv6] layers are rewritten according to `scheme`,
+    /// including the addresses of ipv6 routing headers. Checksums covering the addresses are
+    /// adjusted for the change, other fields such as lengths are left as captured.
+    ///
+    /// Note: addresses which are not part of a layer are not anonymized, such as the ip
+    /// header quoted in the payload of an ICMP error message, or addresses in application
+    /// payloads.
+    pub fn anonymize(&mut self, scheme: AnonScheme) -> Result<(), PacketError> {
+        let original = self.layers.clone();
+
+        for layer in self.layers.iter_mut() {
+            if let Some(ether) = get_layer!(layer, Ether) {
+                *layer = Box::new(Ether {
+                    dst: scheme.anonymize_mac(&ether.dst),
+                    src: scheme.anonymize_mac(&ether.src),
+                    ..ether.clone()
+                });
+            } else if let Some(ipv4) = get_layer!(layer, Ipv4) {
+                *layer = Box::new(Ipv4 {
+                    src: scheme.anonymize_ipv4(ipv4.src),
+                    dst: scheme.anonymize_ipv4(ipv4.dst),
+                    ..ipv4.clone()
+                });
+            } else if let Some(ipv6) = get_layer!(layer, Ipv6) {
+                let mut ipv6 = Ipv6 {
+                    src: scheme.anonymize_ipv6(ipv6.src),
+                    dst: scheme.anonymize_ipv6(ipv6.dst),
+                    ..ipv6.clone()
+                };
+                for extension in ipv6.extensions.iter_mut() {
+                    match extension {
+                        Ipv6Extension::Routing(routing) => match &mut routing.data {
+                            Ipv6RoutingData::Type0 { addresses, .. } => {
+                                for address in addresses.iter_mut() {
+                                    *address = scheme.anonymize_ipv6(*address);
+                                }
+                            }
+                            Ipv6RoutingData::Srh { segments, .. } => {
+                                for segment in segments.iter_mut() {
+                                    *segment = scheme.anonymize_ipv6(*segment);
+                                }
+                            }
+                            Ipv6RoutingData::Unknown { .. } => {}
+                        },
+                    }
+                }
+                *layer = Box::new(ipv6);
+            }
+        }
+
+        anonymize::update_checksums(&original, &mut self.layers)?;
+
+        Ok(())
+    }
+
+    /// Decoded question names of the [Dns] and [DnsTcp] layers of the packet
+    ///
+    /// Errors if a name cannot be decoded, such as a name with an invalid compression pointer.
+    /// See [Dns::question_names].
+    pub fn dns_query_names(&self) -> Result<Vec<String>, PacketError> {
+        let mut names = Vec::new();
+
+        for layer in &self.layers {
+            let dns = get_layer!(layer, Dns)
+                .or_else(|| get_layer!(layer, DnsTcp).map(|dns_tcp| &dns_tcp.dns));
+            if let Some(dns) = dns {
+                names.extend(dns.question_names()?);
+            }
+        }
+
+        Ok(names)
+    }
+
+    /// Size of the packet on the wire, in bytes
+    ///
+    /// When the outermost layer is [Ether], this is the serialized length padded to the
+    /// minimum frame length (60 bytes), plus the ethernet overhead not captured in the layers:
+    /// preamble and start frame delimiter (8 bytes), frame check sequence (4 bytes) and
+    /// interframe gap (12 bytes). Otherwise this is the serialized length.
+    pub fn on_wire_len(&self) -> Result<usize, PacketError> {
+        let length = crate::layer::utils::length_of_layers(&self.layers)?;
+
+        match self.layers.first() {
+            Some(layer) if is_layer!(layer, Ether) => Ok(length.max(MIN_FRAME_LENGTH)
+                + PREAMBLE_SFD.len()
+                + FCS_LENGTH
+                + INTERFRAME_GAP_LENGTH),
+            _ => Ok(length),
+        }
+    }
+
+    /// Fraction of the packet bytes parsed into typed layers, between 0 and 1
+    ///
+    /// Bytes of [Raw] layers are not typed. Aggregated over a capture, this measures how much
+    /// of the data is understood by the parser. An empty packet has a coverage of 1.
+    pub fn coverage(&self) -> Result<f32, PacketError> {
+        let mut typed = 0;
+        let mut total = 0;
+        for layer in self.layers.iter() {
+            let length = layer.length()?;
+            if !is_layer!(layer, Raw) {
+                typed += length;
+            }
+            total += length;
+        }
+
+        if total == 0 {
+            return Ok(1.0);
+        }
+
+        Ok(typed as f32 / total as f32)
+    }
+
+    /// Returns true if the packet exceeds the path `mtu` and cannot be fragmented
+    ///
+    /// The length is measured from the outermost [Ipv4] or [Ipv6] layer. An [Ipv4] packet can only
+    /// be fragmented by routers if the Don't Fragment flag is unset, [Ipv6] packets are never
+    /// fragmented along the path. Such a packet would trigger an ICMP "fragmentation needed"
+    /// or "packet too big" error. Returns false if the packet has no ip layer.
+    pub fn path_mtu_exceeded(&self, mtu: usize) -> Result<bool, PacketError> {
+        let index = self
+            .layers
+            .iter()
+            .position(|layer| is_layer!(layer, Ipv4) || is_layer!(layer, Ipv6));
+
+        let index = match index {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+
+        let dont_fragment = match get_layer!(self.layers[index], Ipv4) {
+            Some(ipv4) => ipv4.dont_fragment(),
+            None => true,
+        };
+
+        Ok(dont_fragment && crate::layer::utils::length_of_layers(&self.layers[index..])? > mtu)
+    }
+
+    /// Compare the declared length fields of each layer against the serialized data
+    ///
+    /// A [LengthReport] is returned for each layer declaring a length field,
+    /// see [LayerExt::length_field]. This is useful to find packets with an invalid length.
+    pub fn length_report(&self) -> Result<Vec<LengthReport>, PacketError> {
+        let mut report = Vec::new();
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            if let Some(field) = layer.length_field() {
+                let actual = if field.includes_header {
+                    crate::layer::utils::length_of_layers(&self.layers[index..])?
+                } else {
+                    crate::layer::utils::length_of_layers(&self.layers[index + 1..])?
+                        + field.extensions_length
+                };
+
+                report.push(LengthReport {
+                    index,
+                    field: field.name,
+                    declared: field.value,
+                    actual,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Declared length field of a layer compared to the serialized data, see [Packet::length_report]
+#[derive(Debug, PartialEq, Clone)]
+pub struct LengthReport {
+    /// Index of the layer in the packet
+    pub index: usize,
+    /// Name of the length field
+    pub field: &'static str,
+    /// Declared length in bytes
+    pub declared: u16,
+    /// Actual length in bytes of the serialized data covered by the field
+    pub actual: usize,
+}
+
+impl LengthReport {
+    /// Returns true if the declared length matches the serialized data
+    pub fn is_valid(&self) -> bool {
+        usize::from(self.declared) == self.actual
+    }
 }
 
 impl Default for Packet {
     fn default() -> Self {
-        Self { layers: Vec::new() }
+        Self {
+            layers: Vec::new(),
+            timestamp: None,
+        }
     }
 }
 
+/// Function parsing a layer from bytes, see [LayerExt::parse_layer]
+pub type LayerParser = fn(&[u8]) -> Result<(&[u8], Box<dyn LayerExt>), LayerError>;
+
 type LayerBinding = Box<
     dyn Fn(
         &dyn LayerExt,
@@ -101,6 +581,7 @@ existing behaviour.
 */
 pub struct PacketParser {
     layer_bindings: HashMap<TypeId, Vec<LayerBinding>>,
+    default_parser: Option<LayerParser>,
 }
 
 impl PacketParser {
@@ -110,12 +591,28 @@ impl PacketParser {
     }
 
     /// Create a packet parser without any default bindings
+    ///
+    /// No default parser is set, un-matched data is returned un-parsed.
     pub fn without_bindings() -> Self {
         PacketParser {
             layer_bindings: HashMap::new(),
+            default_parser: None,
         }
     }
 
+    /// Create a packet parser with the bindings of a [ParserConfig](self::ParserConfig)
+    ///
+    /// No default bindings are added. See [config](self::config) for the supported layer names.
+    pub fn from_config(config: ParserConfig) -> Result<Self, PacketError> {
+        let mut pb = PacketParser::without_bindings();
+
+        for binding in config.bindings.iter() {
+            binding.bind(&mut pb)?;
+        }
+
+        Ok(pb)
+    }
+
     /**
     Add a layer binding to the packet parser
 
@@ -217,6 +714,43 @@ impl PacketParser {
         ));
     }
 
+    /**
+    Set the parser used when no layer binding matches the remaining data
+
+    The default parser of [PacketParser::new] is [Raw]. This allows parsing un-matched data,
+    such as an unknown transport, as a custom layer.
+
+    # Example
+
+    ```rust
+    # use hatchet::{is_layer, packet::PacketParser, layer::{ether::Ether, raw::Raw, LayerExt}};
+    let mut packet_parser = PacketParser::without_bindings();
+    packet_parser.set_default_parser(Raw::parse_layer);
+
+    // Ether / data
+    let input = b"\xff\xff\xff\xff\xff\xff\x00\x00\x00\x00\x00\x00\x08\x00data";
+    let (rest, packet) = packet_parser.parse_packet::<Ether>(input).unwrap();
+    assert!(rest.is_empty());
+    assert!(is_layer!(packet.layers()[1], Raw));
+    ```
+    */
+    pub fn set_default_parser(&mut self, parser: LayerParser) {
+        self.default_parser = Some(parser);
+    }
+
+    /// Find the parser of the layer following `layer` using the layer bindings
+    ///
+    /// Falls back to the default parser if no binding matches
+    fn next_layer_parser(&self, layer: &dyn LayerExt, rest: &[u8]) -> Option<LayerParser> {
+        let tid = layer.as_any().type_id();
+
+        // start from last inserted
+        self.layer_bindings
+            .get(&tid)
+            .and_then(|callbacks| callbacks.iter().rev().find_map(|cb| cb(layer, rest)))
+            .or(self.default_parser)
+    }
+
     /// Parse a packet from bytes, returning the un-parsed data
     pub fn parse_packet<'a, T: LayerExt + 'static>(
         &self,
@@ -241,28 +775,8 @@ impl PacketParser {
                 break;
             }
 
-            let tid = current_layer.as_any().type_id();
-            let callbacks = self.layer_bindings.get(&tid);
-
             // Using the layer bindings, find the parser for the next layer
-            let next_layer_parser = if let Some(callbacks) = callbacks {
-                // labelled loop used here to break out early from for loop
-                #[allow(clippy::never_loop)]
-                'lbl: loop {
-                    // start from last inserted
-                    for cb in callbacks.iter().rev() {
-                        let parser = cb(current_layer.as_ref(), rest);
-
-                        if parser.is_some() {
-                            break 'lbl parser;
-                        }
-                    }
-
-                    break None;
-                }
-            } else {
-                None
-            };
+            let next_layer_parser = self.next_layer_parser(current_layer.as_ref(), rest);
 
             // Next layer becomes the current layer
             if let Some(next_layer_parser) = next_layer_parser {
@@ -280,6 +794,121 @@ impl PacketParser {
 
         Ok((rest, Packet::from_layers(layers)))
     }
+
+    /**
+    Parse back-to-back packets from bytes, until the input is exhausted
+
+    Each packet is parsed from the data left un-parsed by the previous packet, see
+    [parse_packet](Self::parse_packet). Parsing stops at the first error, which is the last
+    element returned, or when a packet does not consume any data.
+
+    A packet with an [Ipv4] or [Ipv6] layer ends with the data covered by the total length
+    or payload length of its first ip layer, data of the packet left un-parsed by the
+    bindings is kept in a trailing [Raw] layer. Zero lengths, such as in captures with TCP
+    Segmentation Offload, and lengths shorter than the ip header are not used.
+
+    Other packets must leave the data of the following packets un-parsed. As the default
+    parser of [new](Self::new) parses remaining data as [Raw], such a packet would consume
+    the whole input, use a parser without a default parser instead.
+
+    Note: ethernet frames padded to the minimum frame length are not supported, the padding
+    is parsed as the start of the next packet.
+
+    # Example
+
+    ```rust
+    # use hatchet::{layer::ether::Ether, packet::PacketParser};
+    # use hexlit::hex;
+    // Ether / Ether
+    let input = hex!("feff20000100 000001000000 0800 feff20000100 000001000000 86dd");
+    let packets = PacketParser::without_bindings().parse_many::<Ether>(&input);
+    assert_eq!(2, packets.len());
+    ```
+    */
+    pub fn parse_many<T: LayerExt + 'static>(
+        &self,
+        input: &[u8],
+    ) -> Vec<Result<Packet, PacketError>> {
+        let mut packets = Vec::new();
+
+        let mut rest = input;
+        while !rest.is_empty() {
+            match self.parse_record::<T>(rest) {
+                Ok((new_rest, packet)) => {
+                    packets.push(Ok(packet));
+
+                    if new_rest.len() == rest.len() {
+                        break;
+                    }
+                    rest = new_rest;
+                }
+                Err(e) => {
+                    packets.push(Err(e));
+                    break;
+                }
+            }
+        }
+
+        packets
+    }
+
+    /// Parse a single packet of [parse_many](Self::parse_many), bounded by its ip length
+    fn parse_record<'a, T: LayerExt + 'static>(
+        &self,
+        input: &'a [u8],
+    ) -> Result<(&'a [u8], Packet), PacketError> {
+        let (rest, packet) = self.parse_packet::<T>(input)?;
+
+        // re-parse the packet unless it ends at its ip length, or the input is truncated
+        let consumed = input.len() - rest.len();
+        let length = match Self::ip_bound(&packet)? {
+            Some(length) if length != consumed && length <= input.len() => length,
+            _ => return Ok((rest, packet)),
+        };
+
+        let (record, rest) = input.split_at(length);
+        let (record_rest, mut packet) = self.parse_packet::<T>(record)?;
+        if !record_rest.is_empty() {
+            packet.layers.push(Box::new(Raw {
+                data: record_rest.to_vec(),
+                bit_offset: 0,
+            }));
+        }
+
+        Ok((rest, packet))
+    }
+
+    /// Length of a packet up to the end of the data covered by its first ip layer
+    fn ip_bound(packet: &Packet) -> Result<Option<usize>, PacketError> {
+        let mut offset = 0;
+
+        for layer in packet.layers.iter() {
+            let header_length = layer.length()?;
+
+            if is_layer!(layer, Ipv4) || is_layer!(layer, Ipv6) {
+                let field = match layer.length_field() {
+                    Some(field) if field.value != 0 => field,
+                    _ => return Ok(None),
+                };
+
+                let length = if field.includes_header {
+                    usize::from(field.value)
+                } else {
+                    header_length - field.extensions_length + usize::from(field.value)
+                };
+
+                return Ok(if length >= header_length {
+                    Some(offset + length)
+                } else {
+                    None
+                });
+            }
+
+            offset += header_length;
+        }
+
+        Ok(None)
+    }
 }
 
 impl Default for PacketParser {
@@ -291,10 +920,22 @@ impl Default for PacketParser {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{
-        get_layer,
-        layer::{Layer, LayerError, LayerExt},
+    use crate::layer::ether::{EtherType, MacAddress};
+    use crate::layer::icmp::{Icmp4, Icmp6};
+    use crate::layer::ip::IpProtocol;
+    use crate::layer::tcp::TcpOption;
+    use crate::layer::{
+        cdp::{Cdp, CdpTlv, CDP_SNAP_OUI},
+        dns::{Dns, DnsTcp},
+        gre::Gre,
+        llc::{Llc, Snap},
+        vxlan::Vxlan,
+        Layer,
     };
+    use crate::packet::config::BindingConfig;
+    use core::any::Any;
+    use hexlit::hex;
+    use rstest::*;
 
     macro_rules! declare_test_layer {
         ($name:ident, $bytes:tt) => {
@@ -357,6 +998,818 @@ mod tests {
         assert_eq!(2, packet.layers_mut().len());
     }
 
+    #[test]
+    fn test_packet_from_hex() {
+        // Ether / IP / TCP / HTTP
+        let packet = Packet::from_hex(
+            "ffffffffffff000000000000 0800
+             450000330001000040067cc27f0000017f000001
+             00140050000000000000000050022000ffa20000
+             474554202f6578616d706c6520485454502f312e31",
+        )
+        .unwrap();
+
+        let layers = packet.layers();
+        assert_eq!(4, layers.len());
+        assert!(is_layer!(layers[0], Ether));
+        assert!(is_layer!(layers[1], Ipv4));
+        assert!(is_layer!(layers[2], Tcp));
+        assert!(is_layer!(layers[3], Raw));
+        assert_eq!(
+            hex!("ffffffffffff0000000000000800450000330001000040067cc27f0000017f00000100140050000000000000000050022000ffa20000474554202f6578616d706c6520485454502f312e31").to_vec(),
+            packet.to_bytes().unwrap()
+        );
+    }
+
+    #[rstest(input, expected,
+        case::odd("fff", PacketError::InvalidHex("odd number of hex digits".to_string())),
+        case::invalid("ffzz", PacketError::InvalidHex("invalid hex digits: zz".to_string())),
+        case::incomplete("ffff", PacketError::Incomplete(1)),
+    )]
+    fn test_packet_from_hex_invalid(input: &str, expected: PacketError) {
+        assert_eq!(Some(expected), Packet::from_hex(input).err());
+    }
+
+    #[test]
+    fn test_packet_timestamp() {
+        let mut packet = Packet::new();
+        assert_eq!(None, packet.timestamp());
+
+        packet.set_timestamp(Duration::new(1627049641, 265361000));
+        assert_eq!(
+            Some(Duration::new(1627049641, 265361000)),
+            packet.timestamp()
+        );
+    }
+
+    #[test]
+    fn test_packet_pop() {
+        // Ether / IP / TCP
+        let test_data = hex!("ffffffffffff0000000000000800450000280001000040067cc97f0000017f00000100140050000000000000000050022000917c0000");
+        let (_rest, mut packet) = PacketParser::new()
+            .parse_packet::<Ether>(&test_data)
+            .unwrap();
+        assert_eq!(3, packet.layers().len());
+
+        let ether = packet.pop_front().unwrap();
+        assert!(is_layer!(ether, Ether));
+
+        let layers = packet.layers();
+        assert_eq!(2, layers.len());
+        assert!(is_layer!(layers[0], Ipv4));
+        assert!(is_layer!(layers[1], Tcp));
+        assert_eq!(test_data[14..].to_vec(), packet.to_bytes().unwrap());
+
+        let tcp = packet.pop_back().unwrap();
+        assert!(is_layer!(tcp, Tcp));
+        assert_eq!(1, packet.layers().len());
+
+        let mut packet = Packet::new();
+        assert!(packet.pop_front().is_none());
+        assert!(packet.pop_back().is_none());
+    }
+
+    #[test]
+    fn test_packet_clone_metadata() {
+        let mut packet = Packet::from_layers(vec![Box::new(Layer0::new())]);
+        packet.set_timestamp(Duration::new(1627049641, 265361000));
+
+        // Metadata follows a clone
+        let mut cloned = packet.clone();
+        assert_eq!(packet.timestamp(), cloned.timestamp());
+
+        cloned.clear_metadata();
+        assert_eq!(None, cloned.timestamp());
+        assert_eq!(1, cloned.layers().len());
+        assert!(packet.timestamp().is_some());
+    }
+
+    #[test]
+    fn test_packet_finalize_layer() {
+        // Ether / IP / TCP / HTTP
+        let test_data = hex!("ffffffffffff0000000000000800450000330001000040067cc27f0000017f00000100140050000000000000000050022000ffa20000474554202f6578616d706c6520485454502f312e31");
+        let (_rest, mut packet) = PacketParser::new()
+            .parse_packet::<Ether>(&test_data)
+            .unwrap();
+
+        // Edit the payload, keeping the same length
+        packet.layers_mut()[3] = Box::new(Raw {
+            data: b"GET /example HTTP/1.0".to_vec(),
+            bit_offset: 0,
+        });
+
+        packet.finalize_layer(2).unwrap();
+
+        let tcp = get_layer!(packet.layers()[2], Tcp).unwrap();
+        assert_eq!(0x4e6b, tcp.checksum);
+
+        // Ip layer is untouched
+        let ipv4 = get_layer!(packet.layers()[1], Ipv4).unwrap();
+        assert_eq!(0x7cc2, ipv4.checksum);
+
+        assert_eq!(
+            Err(PacketError::LayerError(LayerError::Finalize(
+                "layer index 4 out of range for packet of 4 layers".to_string()
+            ))),
+            packet.finalize_layer(4)
+        );
+    }
+
+    #[test]
+    fn test_packet_parse_lenient_options() {
+        // Ether / IP (truncated option) / TCP (unknown option) / Raw
+        let input = hex!("ffffffffffff00000000000008004600003200010000400670bc7f0000017f00000101070a0004d2005000000001000000006002040000000000011e02006869");
+
+        let pb = PacketParser::new();
+        assert!(pb.parse_packet::<Ether>(&input).is_err());
+
+        let mut pb = PacketParser::new();
+        pb.bind_layer(|ether: &Ether, _rest| match ether.ether_type {
+            EtherType::IPv4 => Some(Ipv4::parse_layer_lenient),
+            _ => None,
+        });
+        pb.bind_layer(|ipv4: &Ipv4, _rest| match ipv4.protocol {
+            IpProtocol::TCP => Some(Tcp::parse_layer_lenient),
+            _ => None,
+        });
+
+        let (rest, packet) = pb.parse_packet::<Ether>(&input).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(4, packet.layers().len());
+
+        let ipv4 = get_layer!(packet.layers()[1], Ipv4).unwrap();
+        assert_eq!(vec![0x07, 0x0a, 0x00], ipv4.options_raw);
+        let tcp = get_layer!(packet.layers()[2], Tcp).unwrap();
+        assert_eq!(vec![0x1e, 0x02, 0x00], tcp.options_raw);
+
+        assert_eq!(input.to_vec(), packet.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_packet_template_for() {
+        let packet = Packet::template_for::<Udp>();
+        let layers = packet.layers();
+        assert_eq!(3, layers.len());
+        assert!(is_layer!(layers[0], Ether));
+        assert!(is_layer!(layers[1], Ipv4));
+        assert!(is_layer!(layers[2], Udp));
+        assert_eq!(
+            IpProtocol::UDP,
+            get_layer!(layers[1], Ipv4).unwrap().protocol
+        );
+        assert_eq!(28, get_layer!(layers[1], Ipv4).unwrap().length);
+        assert_eq!(8, get_layer!(layers[2], Udp).unwrap().length);
+        packet.validate().unwrap();
+
+        let packet = Packet::template_for::<Ipv6>();
+        let layers = packet.layers();
+        assert_eq!(2, layers.len());
+        assert_eq!(
+            EtherType::IPv6,
+            get_layer!(layers[0], Ether).unwrap().ether_type
+        );
+        packet.validate().unwrap();
+
+        let packet = Packet::template_for::<Icmp6>();
+        let layers = packet.layers();
+        assert_eq!(3, layers.len());
+        assert_eq!(
+            EtherType::IPv6,
+            get_layer!(layers[0], Ether).unwrap().ether_type
+        );
+        let ipv6 = get_layer!(layers[1], Ipv6).unwrap();
+        assert_eq!(IpProtocol::IPV6ICMP, ipv6.next_header);
+        assert_eq!(8, ipv6.length);
+        assert_ne!(0, get_layer!(layers[2], Icmp6).unwrap().checksum);
+        packet.validate().unwrap();
+
+        let packet = Packet::template_for::<Raw>();
+        assert_eq!(1, packet.layers().len());
+    }
+
+    #[rstest(packet, expected, reparse,
+        case::dns(
+            Packet::template_for::<Dns>(),
+            vec![TypeId::of::<Ether>(), TypeId::of::<Ipv4>(), TypeId::of::<Udp>(), TypeId::of::<Dns>()],
+            true
+        ),
+        case::dns_tcp(
+            Packet::template_for::<DnsTcp>(),
+            vec![TypeId::of::<Ether>(), TypeId::of::<Ipv4>(), TypeId::of::<Tcp>(), TypeId::of::<DnsTcp>()],
+            true
+        ),
+        // vxlan is only bound when carrying an ethernet frame
+        case::vxlan(
+            Packet::template_for::<Vxlan>(),
+            vec![TypeId::of::<Ether>(), TypeId::of::<Ipv4>(), TypeId::of::<Udp>(), TypeId::of::<Vxlan>()],
+            false
+        ),
+        case::gre(
+            Packet::template_for::<Gre>(),
+            vec![TypeId::of::<Ether>(), TypeId::of::<Ipv4>(), TypeId::of::<Gre>()],
+            true
+        ),
+        case::cdp(
+            Packet::template_for::<Cdp>(),
+            vec![TypeId::of::<Ether>(), TypeId::of::<Llc>(), TypeId::of::<Snap>(), TypeId::of::<Cdp>()],
+            true
+        ),
+    )]
+    fn test_packet_template_for_bindings(packet: Packet, expected: Vec<TypeId>, reparse: bool) {
+        let type_ids = |packet: &Packet| -> Vec<TypeId> {
+            packet
+                .layers()
+                .iter()
+                .map(|layer| Any::type_id(layer.as_any()))
+                .collect()
+        };
+        assert_eq!(expected, type_ids(&packet));
+        packet.validate().unwrap();
+
+        if reparse {
+            let bytes = packet.to_bytes().unwrap();
+            let (rest, parsed) = PacketParser::new().parse_packet::<Ether>(&bytes).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(expected, type_ids(&parsed));
+            assert_eq!(bytes, parsed.to_bytes().unwrap());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "finalizing a template of default layers should not fail")]
+    fn test_packet_template_for_finalize_error() {
+        #[derive(Debug, Default, Clone)]
+        struct FinalizeError {}
+        impl Layer for FinalizeError {}
+        impl LayerExt for FinalizeError {
+            fn finalize(
+                &mut self,
+                _prev: &[LayerOwned],
+                _next: &[LayerOwned],
+            ) -> Result<(), LayerError> {
+                Err(LayerError::Finalize("finalize error".to_string()))
+            }
+
+            fn parse(_input: &[u8]) -> Result<(&[u8], Self), LayerError> {
+                unimplemented!()
+            }
+
+            fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
+                Ok(Vec::new())
+            }
+        }
+
+        Packet::template_for::<FinalizeError>();
+    }
+
+    #[test]
+    fn test_packet_descend() {
+        // Ether / IP / UDP / VXLAN / Ether / IP / ICMP
+        let input = hex!("ffffffffffff000000000000080045000052000100004011f946c0a80001c0a80002c00012b5003e00000800000000007b0002000000000202000000000108004500002000010000400166da0a0000010a000002080006fa1234000170696e67");
+
+        let shallow = PacketParser::from_config(ParserConfig {
+            bindings: vec![
+                BindingConfig::new("ether", 0x0800, "ipv4"),
+                BindingConfig::new("ipv4", 17, "udp"),
+                BindingConfig::new("udp", 4789, "raw"),
+            ],
+        })
+        .unwrap();
+
+        let parser = PacketParser::new();
+
+        let (_rest, mut packet) = shallow.parse_packet::<Ether>(&input).unwrap();
+        assert_eq!(4, packet.layers().len());
+        assert!(is_layer!(packet.layers()[3], Raw));
+
+        assert!(packet.descend_with(&parser).unwrap());
+        assert_eq!(5, packet.layers().len());
+        assert_eq!(123, get_layer!(packet.layers()[3], Vxlan).unwrap().vni);
+        assert!(is_layer!(packet.layers()[4], Raw));
+
+        assert!(packet.descend_with(&parser).unwrap());
+        assert_eq!(6, packet.layers().len());
+        assert!(is_layer!(packet.layers()[4], Ether));
+
+        while packet.descend().unwrap() {}
+        let layers = packet.layers();
+        assert_eq!(7, layers.len());
+        assert!(is_layer!(layers[5], Ipv4));
+        assert!(is_layer!(layers[6], Icmp4));
+        assert!(!packet.descend().unwrap());
+        assert!(!packet.descend_with(&parser).unwrap());
+
+        assert_eq!(input.to_vec(), packet.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_packet_on_wire_len() {
+        // Ether / IP / TCP / HTTP
+        let packet = Packet::from_hex(
+            "ffffffffffff000000000000 0800
+            450000280001000040067cce7f0000017f000001
+            0014005000000000000000005002200091ea0000",
+        )
+        .unwrap();
+        assert_eq!(54, packet.to_bytes().unwrap().len());
+        // padded to the minimum frame length
+        assert_eq!(60 + 24, packet.on_wire_len().unwrap());
+
+        let packet = Packet::from_layers(vec![
+            Box::new(Ether::default()),
+            Box::new(Raw {
+                data: vec![0; 100],
+                bit_offset: 0,
+            }),
+        ]);
+        assert_eq!(114 + 24, packet.on_wire_len().unwrap());
+
+        let packet = Packet::from_layers(vec![Box::new(Raw {
+            data: vec![0; 10],
+            bit_offset: 0,
+        })]);
+        assert_eq!(10, packet.on_wire_len().unwrap());
+
+        assert_eq!(0, Packet::new().on_wire_len().unwrap());
+    }
+
+    #[derive(Debug, Clone)]
+    struct UnknownTransport {
+        data: Vec<u8>,
+    }
+    impl Layer for UnknownTransport {}
+    impl LayerExt for UnknownTransport {
+        fn finalize(
+            &mut self,
+            _prev: &[LayerOwned],
+            _next: &[LayerOwned],
+        ) -> Result<(), LayerError> {
+            Ok(())
+        }
+
+        fn parse(input: &[u8]) -> Result<(&[u8], Self), LayerError> {
+            Ok((
+                &input[input.len()..],
+                UnknownTransport {
+                    data: input.to_vec(),
+                },
+            ))
+        }
+
+        fn to_bytes(&self) -> Result<Vec<u8>, LayerError> {
+            Ok(self.data.clone())
+        }
+    }
+
+    #[test]
+    fn test_packet_map_layers() {
+        // Ether / IP (protocol 253) / data
+        let input =
+            hex!("ffffffffffff0000000000000800450000180001000040fd7be67f0000017f00000164617461");
+        let (_rest, mut packet) = PacketParser::new().parse_packet::<Ether>(&input).unwrap();
+        packet.set_timestamp(Duration::from_secs(1));
+        assert!(is_layer!(packet.layers()[2], Raw));
+
+        let packet = packet.map_layers(|layer| match get_layer!(layer, Raw) {
+            Some(raw) => UnknownTransport::parse_layer(&raw.data).unwrap().1,
+            None => layer,
+        });
+
+        let layers = packet.layers();
+        assert_eq!(3, layers.len());
+        assert!(is_layer!(layers[0], Ether));
+        assert!(is_layer!(layers[1], Ipv4));
+        assert_eq!(
+            b"data".to_vec(),
+            get_layer!(layers[2], UnknownTransport).unwrap().data
+        );
+        assert_eq!(Some(Duration::from_secs(1)), packet.timestamp());
+        assert_eq!(input.to_vec(), packet.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_packet_parser_default_parser() {
+        // Ether / IP (protocol 253) / data
+        let input =
+            hex!("ffffffffffff0000000000000800450000180001000040fd7be67f0000017f00000164617461");
+
+        let (rest, packet) = PacketParser::new().parse_packet::<Ether>(&input).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(3, packet.layers().len());
+        assert!(is_layer!(packet.layers()[2], Raw));
+
+        let mut pb = PacketParser::new();
+        pb.set_default_parser(UnknownTransport::parse_layer);
+        let (rest, packet) = pb.parse_packet::<Ether>(&input).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(3, packet.layers().len());
+        assert_eq!(
+            b"data".to_vec(),
+            get_layer!(packet.layers()[2], UnknownTransport)
+                .unwrap()
+                .data
+        );
+
+        // Without a default parser, un-matched data is returned
+        let mut pb = PacketParser::without_bindings();
+        pb.bind_layer(|_ether: &Ether, _rest| Some(Ipv4::parse_layer));
+        let (rest, packet) = pb.parse_packet::<Ether>(&input).unwrap();
+        assert_eq!(b"data", rest);
+        assert_eq!(2, packet.layers().len());
+    }
+
+    #[test]
+    fn test_packet_parse_ipv4_zero_length() {
+        // Ether / IP (length 0, TCP Segmentation Offload) / TCP / data
+        let input = hex!("ffffffffffff000000000000080045000000000140004006b788c0a8010ac0a801149c4001bb00000001000000015018040000000000000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f");
+
+        let (rest, packet) = PacketParser::new().parse_packet::<Ether>(&input).unwrap();
+        assert!(rest.is_empty());
+
+        let layers = packet.layers();
+        assert_eq!(4, layers.len());
+        assert_eq!(0, get_layer!(layers[1], Ipv4).unwrap().length);
+        assert!(is_layer!(layers[2], Tcp));
+        assert_eq!(
+            (0..64).collect::<Vec<u8>>(),
+            get_layer!(layers[3], Raw).unwrap().data
+        );
+        assert_eq!(input.to_vec(), packet.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_packet_anonymize() {
+        let scheme = AnonScheme::PrefixPreserving { key: 0xdead_beef };
+
+        // Ether / IP / TCP, same source ip and different destinations
+        let mut packets = [
+            Packet::from_hex(
+                "ffffffffffff020000000001 0800
+                450000280001000040060000c0a8010a08080808
+                0014005000000000000000005002200000000000",
+            )
+            .unwrap(),
+            Packet::from_hex(
+                "ffffffffffff020000000001 0800
+                450000280001000040060000c0a8010a01010101
+                0014005000000000000000005002200000000000",
+            )
+            .unwrap(),
+        ];
+        for packet in packets.iter_mut() {
+            packet.finalize().unwrap();
+            packet.anonymize(scheme).unwrap();
+        }
+
+        let ipv4_0 = get_layer!(packets[0].layers()[1], Ipv4).unwrap();
+        let ipv4_1 = get_layer!(packets[1].layers()[1], Ipv4).unwrap();
+        assert_ne!(0xc0a8010a, ipv4_0.src);
+        assert_eq!(ipv4_0.src, ipv4_1.src);
+        assert_ne!(ipv4_0.dst, ipv4_1.dst);
+
+        let ether_0 = get_layer!(packets[0].layers()[0], Ether).unwrap();
+        let ether_1 = get_layer!(packets[1].layers()[0], Ether).unwrap();
+        assert_eq!(ether_0.src, ether_1.src);
+        assert_eq!(MacAddress([0xff; 6]), ether_0.dst);
+
+        // checksums are updated
+        for packet in packets.iter() {
+            let bytes = packet.to_bytes().unwrap();
+            let (_rest, reparsed) = PacketParser::new().parse_packet::<Ether>(&bytes).unwrap();
+            let mut finalized = reparsed.clone();
+            finalized.finalize().unwrap();
+            assert_eq!(bytes, finalized.to_bytes().unwrap());
+        }
+    }
+
+    #[rstest(
+        input,
+        case::vxlan(
+            // Ether / IP / UDP / VXLAN / Ether / IP / ICMP
+            "ffffffffffff020000000001 0800
+            450000000001000040110000 c0a80001 c0a80002
+            c00012b5 0000 0000
+            0800000000007b00
+            020000000002 020000000001 0800
+            450000000001000040010000 0a000001 0a000002
+            0800000012340001 70696e67"
+        ),
+        case::gre_checksum(
+            // Ether / IP / GRE(checksum) / IP / UDP / Raw
+            "ffffffffffff020000000001 0800
+            4500000000010000402f0000 c0a80001 c0a80002
+            80000800 00000000
+            450000000001000040110000 0a000001 0a000002
+            04d2162e 0000 0000 68656c6c6f"
+        ),
+        case::ipv6_srh(
+            // Ether / IPv6 / SRH / UDP / Raw
+            "ffffffffffff020000000001 86dd
+            6000000000002b40 20010db80000000000000000000000aa 20010db8000000000000000000000001
+            1104040101000000 20010db8000000000000000000000002 20010db8000000000000000000000001
+            04d2162e 0000 0000 68656c6c6f"
+        ),
+    )]
+    fn test_packet_anonymize_checksums(input: &str) {
+        let scheme = AnonScheme::PrefixPreserving { key: 0xdead_beef };
+
+        let mut packet = Packet::from_hex(input).unwrap();
+        packet.finalize().unwrap();
+        let length = packet.to_bytes().unwrap().len();
+
+        packet.anonymize(scheme).unwrap();
+
+        let bytes = packet.to_bytes().unwrap();
+        assert_eq!(length, bytes.len());
+
+        let mut finalized = packet.clone();
+        finalized.finalize().unwrap();
+        assert_eq!(bytes, finalized.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_packet_anonymize_ipv6_routing() {
+        let scheme = AnonScheme::PrefixPreserving { key: 0xdead_beef };
+
+        // Ether / IPv6 / Type0 routing / UDP
+        let mut packet = Packet::from_hex(
+            "ffffffffffff020000000001 86dd
+            6000000000202b40 20010db80000000000000000000000aa 20010db8000000000000000000000001
+            1102000100000000 20010db8000000000000000000000002
+            04d2162e 0008 0000",
+        )
+        .unwrap();
+        packet.anonymize(scheme).unwrap();
+
+        let ipv6 = get_layer!(packet.layers()[1], Ipv6).unwrap();
+        assert_eq!(
+            scheme.anonymize_ipv6(0x20010db8000000000000000000000002),
+            ipv6.final_destination()
+        );
+    }
+
+    #[test]
+    fn test_packet_anonymize_truncated() {
+        let scheme = AnonScheme::PrefixPreserving { key: 0xdead_beef };
+
+        // Ether / IP / UDP / Raw
+        let mut packet = Packet::from_hex(
+            "ffffffffffff020000000001 0800
+            450000000001000040110000 c0a80001 c0a80002
+            04d2162e 0000 0000
+            000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+        )
+        .unwrap();
+        packet.finalize().unwrap();
+
+        // the capture is truncated after 4 bytes of udp payload
+        let bytes = packet.to_bytes().unwrap();
+        let (_rest, mut truncated) = PacketParser::new()
+            .parse_packet::<Ether>(&bytes[..14 + 20 + 8 + 4])
+            .unwrap();
+
+        packet.anonymize(scheme).unwrap();
+        truncated.anonymize(scheme).unwrap();
+
+        // lengths are left as captured and checksums match the complete packet
+        let ipv4 = get_layer!(truncated.layers()[1], Ipv4).unwrap();
+        let udp = get_layer!(truncated.layers()[2], Udp).unwrap();
+        assert_eq!(20 + 8 + 32, ipv4.length);
+        assert_eq!(8 + 32, udp.length);
+        assert_eq!(get_layer!(packet.layers()[1], Ipv4).unwrap(), ipv4);
+        assert_eq!(get_layer!(packet.layers()[2], Udp).unwrap(), udp);
+    }
+
+    #[test]
+    fn test_packet_dns_query_names() {
+        // Ether / IP / UDP / DNS(qd=DNSQR(qname="www.example.com"))
+        let packet = Packet::from_hex(
+            "ffffffffffff020000000001 0800
+            4500003d0001000040113a55c0a8000108080808
+            d43100350029b2f5
+            abcd01000001000000000000 03777777076578616d706c6503636f6d00 00010001",
+        )
+        .unwrap();
+        assert!(is_layer!(packet.layers()[3], Dns));
+        assert_eq!(
+            vec!["www.example.com".to_string()],
+            packet.dns_query_names().unwrap()
+        );
+
+        // DNS(qd=DNSQR(qname=pointer to itself))
+        let packet = Packet::from_hex(
+            "ffffffffffff020000000001 0800
+            450000280001000040113a6ac0a8000108080808
+            d43100350014b2f5
+            abcd01000001000000000000 c00c 00010001",
+        )
+        .unwrap();
+        assert!(is_layer!(packet.layers()[3], Dns));
+        assert_eq!(
+            Err(PacketError::LayerError(LayerError::Parse(
+                "dns name compression pointer loop".to_string()
+            ))),
+            packet.dns_query_names()
+        );
+
+        let packet = Packet::from_hex("ffffffffffff020000000001 0800").unwrap();
+        assert!(packet.dns_query_names().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_packetparser_parse_many() {
+        let mut parser = PacketParser::without_bindings();
+        parser.bind_layer(|_ether: &Ether, _rest| Some(Ipv4::parse_layer));
+        parser.bind_layer(|_ipv4: &Ipv4, _rest| Some(Udp::parse_layer));
+
+        // Ether / IP / UDP, twice
+        let frame = hex!(
+            "feff20000100000001000000 0800
+            4500001c0001000040110000c0a80001c0a80002
+            04d2162e00080000"
+        );
+        let mut input = frame.to_vec();
+        input.extend_from_slice(&frame);
+
+        let packets = parser.parse_many::<Ether>(&input);
+        assert_eq!(2, packets.len());
+        for packet in packets {
+            let packet = packet.unwrap();
+            assert_eq!(3, packet.layers().len());
+            assert!(is_layer!(packet.layers()[2], Udp));
+        }
+
+        // trailing truncated frame
+        input.extend_from_slice(&frame[..10]);
+        let packets = parser.parse_many::<Ether>(&input);
+        assert_eq!(3, packets.len());
+        assert!(packets[0].is_ok());
+        assert!(packets[1].is_ok());
+        assert!(matches!(packets[2], Err(PacketError::Incomplete(_))));
+
+        // the raw default parser consumes the data of a packet up to its ip length
+        let packets = PacketParser::new().parse_many::<Ether>(&input);
+        assert_eq!(3, packets.len());
+        assert!(packets[0].is_ok());
+        assert!(packets[1].is_ok());
+
+        assert!(parser.parse_many::<Ether>(&[]).is_empty());
+    }
+
+    #[rstest(
+        frame,
+        expected_payload,
+        case::ipv4(
+            // Ether / IP / UDP / Raw
+            "feff20000100000001000000 0800
+            450000210001000040110000c0a80001c0a80002
+            04d2162e000d0000 68656c6c6f",
+            hex!("68656c6c6f").to_vec()
+        ),
+        case::ipv6(
+            // Ether / IPv6 / SRH / UDP / Raw
+            "feff20000100000001000000 86dd
+            6000000000352b40 20010db80000000000000000000000aa 20010db8000000000000000000000001
+            1104040101000000 20010db8000000000000000000000002 20010db8000000000000000000000001
+            04d2162e000d0000 68656c6c6f",
+            hex!("68656c6c6f").to_vec()
+        ),
+        case::ipv4_padded_udp(
+            // Ether / IP / UDP / Raw, with data past the udp length covered by the ip length
+            "feff20000100000001000000 0800
+            450000200001000040110000c0a80001c0a80002
+            04d2162e000a0000 68656c6c",
+            hex!("68656c6c").to_vec()
+        ),
+    )]
+    fn test_packetparser_parse_many_payload(frame: &str, expected_payload: Vec<u8>) {
+        let frame = Packet::from_hex(frame).unwrap().to_bytes().unwrap();
+        let mut input = frame.clone();
+        input.extend_from_slice(&frame);
+
+        // without a default parser, the payload is kept in a trailing raw layer
+        let mut parser = PacketParser::without_bindings();
+        parser.bind_layer(|ether: &Ether, _rest| match ether.ether_type {
+            EtherType::IPv4 => Some(Ipv4::parse_layer),
+            EtherType::IPv6 => Some(Ipv6::parse_layer),
+            _ => None,
+        });
+        parser.bind_layer(|_ipv4: &Ipv4, _rest| Some(Udp::parse_layer));
+        parser.bind_layer(|_ipv6: &Ipv6, _rest| Some(Udp::parse_layer));
+
+        for parser in [PacketParser::new(), parser].iter() {
+            let packets = parser.parse_many::<Ether>(&input);
+            assert_eq!(2, packets.len());
+            for packet in packets {
+                let packet = packet.unwrap();
+                assert_eq!(frame, packet.to_bytes().unwrap());
+
+                let layers = packet.layers();
+                assert!(is_layer!(layers[layers.len() - 2], Udp));
+                assert_eq!(
+                    expected_payload,
+                    get_layer!(layers[layers.len() - 1], Raw).unwrap().data
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_packet_coverage() {
+        // Ether / IP / UDP
+        let packet = Packet::from_hex(
+            "feff20000100000001000000 0800
+            4500001c0001000040110000c0a80001c0a80002
+            04d2162e00080000",
+        )
+        .unwrap();
+        assert_eq!(1.0, packet.coverage().unwrap());
+
+        // Ether / IP / UDP / Raw(84 bytes)
+        let packet = Packet::from_hex(&format!(
+            "feff20000100000001000000 0800
+            450000700001000040110000c0a80001c0a80002
+            04d2162f005c0000 {}",
+            "aa".repeat(84)
+        ))
+        .unwrap();
+        assert!(is_layer!(packet.layers()[3], Raw));
+        assert_eq!(42.0 / 126.0, packet.coverage().unwrap());
+
+        assert_eq!(1.0, Packet::new().coverage().unwrap());
+    }
+
+    #[test]
+    fn test_packet_normalized_eq() {
+        let packet = Packet::from_layers(vec![
+            Box::new(Ether::default()),
+            Box::new(Ipv4 {
+                protocol: IpProtocol::UDP,
+                ..Default::default()
+            }),
+            Box::new(Udp::default()),
+            Box::new(Raw {
+                data: vec![0xAA; 10],
+                bit_offset: 0,
+            }),
+        ]);
+
+        let mut finalized = packet.clone();
+        finalized.finalize().unwrap();
+        assert_ne!(packet.to_bytes().unwrap(), finalized.to_bytes().unwrap());
+        assert_eq!(
+            finalized.to_bytes().unwrap(),
+            packet.to_bytes_finalized().unwrap()
+        );
+
+        assert!(packet.normalized_eq(&finalized));
+        assert!(finalized.normalized_eq(&packet));
+
+        let mut different = packet.clone();
+        different.layers_mut()[3] = Box::new(Raw {
+            data: vec![0xBB; 10],
+            bit_offset: 0,
+        });
+        assert!(!packet.normalized_eq(&different));
+
+        // Ether / IP / TCP with options, stale offset and checksum
+        let packet = Packet::from_layers(vec![
+            Box::new(Ether::default()),
+            Box::new(Ipv4 {
+                protocol: IpProtocol::TCP,
+                ..Default::default()
+            }),
+            Box::new(Tcp {
+                options: vec![TcpOption::NOP],
+                ..Default::default()
+            }),
+        ]);
+        let mut finalized = packet.clone();
+        finalized.finalize().unwrap();
+        assert!(packet.normalized_eq(&finalized));
+    }
+
+    #[test]
+    fn test_packet_validate() {
+        let mut packet = Packet::from_layers(vec![
+            Box::new(Ether::default()),
+            Box::new(Ipv4::default()),
+            Box::new(Tcp::default()),
+        ]);
+        packet.validate().unwrap();
+
+        packet.layers_mut()[2] = Box::new(Tcp {
+            offset: 4,
+            ..Default::default()
+        });
+        assert_eq!(
+            Err(PacketError::LayerError(LayerError::Validate(
+                "invalid tcp offset 4".to_string()
+            ))),
+            packet.validate()
+        );
+    }
+
     #[test]
     fn test_packet_to_bytes() {
         let layer0 = Box::new(Layer0::new());
@@ -368,6 +1821,255 @@ mod tests {
         assert_eq!(b"layer0layer1layer2".to_vec(), packet.to_bytes().unwrap());
     }
 
+    #[test]
+    fn test_packet_transport_payload() {
+        // Ether / IP / UDP / DNS
+        let test_data = hex!("ffffffffffff000000000000080045000039000100004011a8f1c0a8010a08080808cf08003500257d02123401000001000000000000076578616d706c6503636f6d0000010001");
+        let (_rest, packet) = PacketParser::new()
+            .parse_packet::<Ether>(&test_data)
+            .unwrap();
+
+        assert_eq!(
+            Some(hex!("123401000001000000000000076578616d706c6503636f6d0000010001").to_vec()),
+            packet.transport_payload().unwrap()
+        );
+
+        let packet = Packet::from_layers(vec![Box::new(Ether::default())]);
+        assert_eq!(None, packet.transport_payload().unwrap());
+    }
+
+    #[test]
+    fn test_packet_parse_dns() {
+        // Ether / IP / UDP / DNS
+        let input = hex!("ffffffffffff000000000000080045000039000100004011a8f1c0a8010a08080808cf08003500257d02123401000001000000000000076578616d706c6503636f6d0000010001");
+
+        let (rest, packet) = PacketParser::new().parse_packet::<Ether>(&input).unwrap();
+        assert!(rest.is_empty());
+
+        let layers = packet.layers();
+        assert_eq!(4, layers.len());
+        assert!(is_layer!(layers[2], Udp));
+        let dns = get_layer!(layers[3], Dns).unwrap();
+        assert_eq!(0x1234, dns.id);
+        assert_eq!(1, dns.questions.len());
+        assert_eq!(input.to_vec(), packet.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_packet_parse_dns_truncated() {
+        // Ether / IP / UDP / Raw, truncated dns question
+        let input = hex!("ffffffffffff00000000000008004500002d000100004011a8fdc0a8010a08080808cf0800350019000012340100000100000000000007657861");
+
+        let (rest, packet) = PacketParser::new().parse_packet::<Ether>(&input).unwrap();
+        assert!(rest.is_empty());
+
+        let layers = packet.layers();
+        assert_eq!(4, layers.len());
+        assert!(is_layer!(layers[2], Udp));
+        assert!(is_layer!(layers[3], Raw));
+        assert_eq!(input.to_vec(), packet.to_bytes().unwrap());
+    }
+
+    #[rstest(input, expected_dns,
+        case::complete(&hex!("ffffffffffff000000000000080045000047000100004006a8eec0a8010a080808089c40003500000001000000005018040000000000001d123401000001000000000000076578616d706c6503636f6d0000010001"), true),
+        case::partial(&hex!("ffffffffffff000000000000080045000034000100004006a901c0a8010a080808089c40003500000001000000005018040000000000001d12340100000100000000"), false),
+        case::not_dns(&hex!("ffffffffffff00000000000008004500002e000100004006a907c0a8010a080808089c40003500000001000000005018040000000000000004deadbeef"), false),
+    )]
+    fn test_packet_parse_dns_tcp(input: &[u8], expected_dns: bool) {
+        let (rest, packet) = PacketParser::new().parse_packet::<Ether>(input).unwrap();
+        assert!(rest.is_empty());
+
+        let layers = packet.layers();
+        assert_eq!(4, layers.len());
+        assert!(is_layer!(layers[2], Tcp));
+        assert_eq!(expected_dns, is_layer!(layers[3], DnsTcp));
+        assert_eq!(!expected_dns, is_layer!(layers[3], Raw));
+    }
+
+    #[rstest(
+        payload,
+        expected_vxlan,
+        case::vxlan("0800000000007b00 020000000002 020000000001 0800", true),
+        case::short("0800", false),
+        case::no_vni("0000000000007b00 020000000002 020000000001 0800", false),
+        case::short_frame("0800000000007b00 0200", false)
+    )]
+    fn test_packet_parse_vxlan(payload: &str, expected_vxlan: bool) {
+        // Ether / IP / UDP(dport=4789) / payload
+        let packet = Packet::from_hex(&format!(
+            "ffffffffffff000000000000 0800
+            450000000001000040110000 c0a80001 c0a80002
+            c00012b5 0000 0000 {}",
+            payload
+        ))
+        .unwrap();
+
+        let layers = packet.layers();
+        assert!(is_layer!(layers[2], Udp));
+        assert_eq!(expected_vxlan, is_layer!(layers[3], Vxlan));
+        assert_eq!(!expected_vxlan, is_layer!(layers[3], Raw));
+    }
+
+    #[test]
+    fn test_packet_parse_cdp() {
+        // Ether(802.3) / LLC / SNAP / CDP
+        let input = hex!("01000ccccccc0019e86f0a0100b4aaaa0300000c200002b433730001000e73772d636f72652d30310005004d436973636f20494f5320536f6674776172652c20433239363020536f667477617265202843323936302d4c414e424153454b392d4d292c2056657273696f6e2031352e30283229534500060019636973636f2057532d43323936302d323454542d4c00020011000000010101cc00040a000001000300164769676162697445746865726e6574302f310004000800000028000b000501");
+
+        let (rest, mut packet) = PacketParser::new().parse_packet::<Ether>(&input).unwrap();
+        assert!(rest.is_empty());
+
+        let layers = packet.layers();
+        assert_eq!(4, layers.len());
+        assert!(get_layer!(layers[1], Llc).unwrap().is_snap());
+        assert_eq!(CDP_SNAP_OUI, get_layer!(layers[2], Snap).unwrap().oui);
+        let cdp = get_layer!(layers[3], Cdp).unwrap();
+        assert_eq!(7, cdp.tlvs.len());
+        assert_eq!(
+            CdpTlv::DeviceId {
+                length: 14,
+                value: b"sw-core-01".to_vec()
+            },
+            cdp.tlvs[0]
+        );
+
+        packet.finalize().unwrap();
+        assert_eq!(input.to_vec(), packet.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_packet_parse_8023_padded() {
+        // Ether(802.3) / LLC / Raw, STP bpdu padded to the minimum frame length
+        let input = hex!("0180c2000000000000000001002642420300000000008000000000000000000000000000800000000000000000000000000000000000000000000000");
+
+        let (rest, packet) = PacketParser::new().parse_packet::<Ether>(&input).unwrap();
+        assert!(rest.is_empty());
+
+        let layers = packet.layers();
+        assert_eq!(3, layers.len());
+        assert!(!get_layer!(layers[1], Llc).unwrap().is_snap());
+        assert!(is_layer!(layers[2], Raw));
+        assert_eq!(input.to_vec(), packet.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_packet_parse_ipv6_routing() {
+        // IPv6 / SRH / UDP
+        let test_data = hex!("6000000000302b4020010db80000000000000000000000aa20010db8000000000000000000000001110404010100000020010db800000000000000000000000220010db800000000000000000000000104d2162e00080000");
+        let (rest, packet) = PacketParser::new()
+            .parse_packet::<Ipv6>(&test_data)
+            .unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(2, packet.layers().len());
+        assert!(is_layer!(packet.layers()[1], Udp));
+    }
+
+    #[rstest(
+        flags,
+        mtu,
+        expected,
+        case::df_exceeded(0b010, 1500, true),
+        case::df_fits(0b010, 2000, false),
+        case::df_exact(0b010, 1528, false),
+        case::no_df(0b000, 1500, false)
+    )]
+    fn test_packet_path_mtu_exceeded(flags: u8, mtu: usize, expected: bool) {
+        let packet = Packet::from_layers(vec![
+            Box::new(Ether::default()),
+            Box::new(Ipv4 {
+                flags,
+                ..Default::default()
+            }),
+            Box::new(Udp::default()),
+            Box::new(Raw {
+                data: vec![0; 1500],
+                bit_offset: 0,
+            }),
+        ]);
+
+        assert_eq!(expected, packet.path_mtu_exceeded(mtu).unwrap());
+    }
+
+    #[test]
+    fn test_packet_path_mtu_exceeded_ipv6() {
+        let packet = Packet::from_layers(vec![
+            Box::new(Ipv6::default()),
+            Box::new(Raw {
+                data: vec![0; 1500],
+                bit_offset: 0,
+            }),
+        ]);
+        assert!(packet.path_mtu_exceeded(1500).unwrap());
+
+        let packet = Packet::from_layers(vec![Box::new(Ether::default())]);
+        assert!(!packet.path_mtu_exceeded(0).unwrap());
+    }
+
+    #[test]
+    fn test_packet_length_report() {
+        let layers: Vec<LayerOwned> = vec![
+            Box::new(Ether::default()),
+            Box::new(Ipv4 {
+                length: 100,
+                ..Default::default()
+            }),
+            Box::new(Udp {
+                length: 13,
+                ..Default::default()
+            }),
+            Box::new(Raw::parse(b"hello").unwrap().1),
+        ];
+        let packet = Packet::from_layers(layers);
+
+        let report = packet.length_report().unwrap();
+        assert_eq!(
+            vec![
+                LengthReport {
+                    index: 1,
+                    field: "total length",
+                    declared: 100,
+                    actual: 33,
+                },
+                LengthReport {
+                    index: 2,
+                    field: "length",
+                    declared: 13,
+                    actual: 13,
+                },
+            ],
+            report
+        );
+        assert!(!report[0].is_valid());
+        assert!(report[1].is_valid());
+    }
+
+    #[test]
+    fn test_packet_length_report_ipv6_routing() {
+        // IPv6 / SRH / UDP, with zeroed lengths
+        let input = hex!("6000000000002b4020010db80000000000000000000000aa20010db8000000000000000000000001110404010100000020010db800000000000000000000000220010db800000000000000000000000104d2162e00000000");
+        let (_rest, mut packet) = PacketParser::new().parse_packet::<Ipv6>(&input).unwrap();
+        packet.finalize().unwrap();
+
+        let report = packet.length_report().unwrap();
+        assert_eq!(
+            vec![
+                LengthReport {
+                    index: 0,
+                    field: "payload length",
+                    declared: 48,
+                    actual: 48,
+                },
+                LengthReport {
+                    index: 1,
+                    field: "length",
+                    declared: 8,
+                    actual: 8,
+                },
+            ],
+            report
+        );
+    }
+
     #[test]
     fn test_packet_finalize_lengths() {
         // test a range on lengths for the packet finalize function
@@ -6,9 +6,14 @@ from scapy.layers.usb import USBpcap
 PWD = Path(__file__).parent.resolve()
 
 
-def generate_pcap(packets, name: str):
+def generate_pcap(packets, name: str, nano: bool = False):
     out_name = os.path.join(PWD, name)
-    wrpcap(out_name, packets)
+    wrpcap(out_name, packets, nano=nano)
+
+
+def with_time(packet, time: str):
+    packet.time = EDecimal(time)
+    return packet
 
 
 # Test read/write combinations
@@ -33,3 +38,10 @@ generate_pcap([
 generate_pcap([
     USBpcap()
 ], 'test_pcap_unhandled_read_write.pcap')
+
+
+# Nanosecond timestamp resolution (magic 0xa1b23c4d)
+generate_pcap([
+    with_time(Ether(type=0x0800)/IP(), "1627049641.123456789"),
+    with_time(Ether(type=0x0800)/IP(), "1627049641.999999999"),
+], 'test_pcap_read_nanosecond.pcap', nano=True)
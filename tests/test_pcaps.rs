@@ -1,9 +1,10 @@
 use hatchet::{
-    datalink::{pcapfile::PcapFile, InterfaceReader},
+    datalink::{pcapfile::PcapFile, InterfaceReader, MultiInterfaceReader},
     is_layer,
     layer::{ether::Ether, raw::Raw},
     packet::Packet,
 };
+use std::time::Duration;
 
 macro_rules! gen_pcap_rw_test {
     ($name:ident, $count:expr, $body:expr) => {
@@ -40,6 +41,58 @@ gen_pcap_rw_test!(test_pcap_read_write, 14, |pkt: &Packet| {
     assert!(is_layer!(first_layer, Ether));
 });
 
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_pcap_read_timestamps() {
+    let interface =
+        InterfaceReader::init::<PcapFile>("./tests/pcaps/test_pcap_read_write.pcap").unwrap();
+
+    let timestamps: Vec<Duration> = interface.map(|pkt| pkt.timestamp().unwrap()).collect();
+
+    assert_eq!(14, timestamps.len());
+    assert_eq!(Duration::new(1627049641, 265361000), timestamps[0]);
+    assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_pcap_read_nanosecond_timestamps() {
+    let interface =
+        InterfaceReader::init::<PcapFile>("./tests/pcaps/test_pcap_read_nanosecond.pcap").unwrap();
+
+    let timestamps: Vec<Duration> = interface.map(|pkt| pkt.timestamp().unwrap()).collect();
+
+    // sub-second field is read as nanoseconds, not microseconds
+    assert_eq!(
+        vec![
+            Duration::new(1627049641, 123456789),
+            Duration::new(1627049641, 999999999),
+        ],
+        timestamps
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_pcap_read_multi() {
+    let readers = vec![
+        InterfaceReader::init::<PcapFile>("./tests/pcaps/test_pcap_read_write.pcap").unwrap(),
+        InterfaceReader::init::<PcapFile>("./tests/pcaps/test_pcap_unhandled_read_write.pcap")
+            .unwrap(),
+    ];
+    let interface = MultiInterfaceReader::new(readers);
+
+    let packets: Vec<Packet> = interface.collect();
+    assert_eq!(15, packets.len());
+
+    // Packets are read in turn from each reader
+    assert!(is_layer!(packets[0].layers()[0], Ether));
+    assert!(is_layer!(packets[1].layers()[0], Raw));
+    assert!(packets[2..]
+        .iter()
+        .all(|pkt| is_layer!(pkt.layers()[0], Ether)));
+}
+
 gen_pcap_rw_test!(test_pcap_unhandled_read_write, 1, |pkt: &Packet| {
     // since these are not handled in hatchet, there should only be a single Raw layer per packet
     assert_eq!(1, pkt.layers().len());
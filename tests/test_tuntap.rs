@@ -0,0 +1,61 @@
+#![cfg(all(feature = "tuntap", target_os = "linux"))]
+
+use hatchet::{
+    datalink::{pnet::Pnet, tuntap::Tap, Interface, PacketRead, PacketWrite},
+    layer::{
+        ether::{Ether, EtherType, MacAddress},
+        raw::Raw,
+    },
+    packet::Packet,
+};
+use std::process::Command;
+
+const TAP_NAME: &str = "hatchet0";
+
+fn test_frame(payload: &[u8]) -> Packet {
+    Packet::from_layers(vec![
+        Box::new(Ether {
+            dst: MacAddress([0xff; 6]),
+            src: MacAddress([0x02, 0, 0, 0, 0, 0x01]),
+            ether_type: EtherType::Unknown(0x88b5),
+        }),
+        Box::new(Raw {
+            data: payload.to_vec(),
+            bit_offset: 0,
+        }),
+    ])
+}
+
+/// Read packets until one matching `expected` is found, ignoring traffic generated by the kernel
+fn read_until<R: PacketRead>(reader: &mut R, expected: &[u8]) -> bool {
+    (0..32).any(|_| reader.read().unwrap().to_bytes().unwrap() == expected)
+}
+
+// Requires CAP_NET_ADMIN, run in CI with `cargo test --features tuntap -- --ignored`
+#[test]
+#[ignore]
+fn test_tap_rw() {
+    let tap = Interface::init::<Tap>(TAP_NAME).unwrap();
+    let (mut tap_rx, mut tap_tx) = tap.into_split();
+
+    let status = Command::new("ip")
+        .args(["link", "set", "dev", TAP_NAME, "up"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let pnet = Interface::init::<Pnet>(TAP_NAME).unwrap();
+    let (mut pnet_rx, mut pnet_tx) = pnet.into_split();
+
+    // Frame written to the tap device is received by the kernel
+    let frame = test_frame(b"to kernel");
+    let expected = frame.to_bytes().unwrap();
+    tap_tx.write(frame).unwrap();
+    assert!(read_until(&mut pnet_rx, &expected));
+
+    // Frame sent by the kernel is read from the tap device
+    let frame = test_frame(b"from kernel");
+    let expected = frame.to_bytes().unwrap();
+    pnet_tx.write(frame).unwrap();
+    assert!(read_until(&mut tap_rx, &expected));
+}